@@ -0,0 +1,260 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Sound effects reacting to the player events, and music of the levels
+/// crossfading when the level changes.
+/// Bevy doesn't play anything when no audio device is found, the sound
+/// effects are then removed after a while so they don't pile up. Without the
+/// Bevy audio plugin, in headless runs, the game stays silent.
+use std::time::Duration;
+
+use bevy::audio::{AudioPlugin, Volume};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::ApplicationState;
+use crate::player::Player;
+use crate::player::movement::{HoldGrab, JustJumped, is_wall_sliding};
+use crate::player::sensing::Landed;
+use crate::world::{CurrentLevel, holds::HoldsVisibility, holds::ShowHolds, levels::LEVELS};
+
+const CROSSFADE_DURATION: f32 = 1.5;
+// Longer than any sound effect, the ones never played are removed after it
+const SFX_LIFETIME: Duration = Duration::from_secs(2);
+// Impact speed giving the landing sound its full volume
+const LOUD_LANDING_SPEED: f32 = 600.0;
+
+pub struct SoundPlugin;
+
+/// Volumes between 0.0 and 1.0, the master volume applies to all the sounds
+#[derive(Resource)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.0,
+            music: 0.6,
+            sfx: 0.8,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SoundEffects {
+    jump: Handle<AudioSource>,
+    land: Handle<AudioSource>,
+    wall_slide: Handle<AudioSource>,
+    grab: Handle<AudioSource>,
+    grab_failed: Handle<AudioSource>,
+    holds_shown: Handle<AudioSource>,
+    holds_hidden: Handle<AudioSource>,
+}
+
+#[derive(Component)]
+struct SoundEffect(Timer);
+
+/// Looping sound, paused when the player is not sliding on a wall
+#[derive(Component)]
+struct WallSlideSound;
+
+#[derive(Component)]
+struct Music {
+    path: &'static str,
+    /// Fade level between 0.0 and 1.0
+    fade: f32,
+    fading_out: bool,
+}
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AudioSettings>();
+        if !app.is_plugin_added::<AudioPlugin>() {
+            warn!("No audio plugin, the game is silent");
+            return;
+        }
+
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (jump_sound, landing_sound, grab_sound, holds_sound)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(Update, (wall_slide_sound, remove_sound_effects));
+        app.add_systems(Update, change_music.run_if(resource_changed::<CurrentLevel>));
+        app.add_systems(Update, fade_music.after(change_music));
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let sounds = SoundEffects {
+        jump: asset_server.load("sounds/jump.wav"),
+        land: asset_server.load("sounds/land.wav"),
+        wall_slide: asset_server.load("sounds/wall_slide.wav"),
+        grab: asset_server.load("sounds/grab.wav"),
+        grab_failed: asset_server.load("sounds/grab_failed.wav"),
+        holds_shown: asset_server.load("sounds/holds_shown.wav"),
+        holds_hidden: asset_server.load("sounds/holds_hidden.wav"),
+    };
+
+    commands.spawn((
+        AudioBundle {
+            source: sounds.wall_slide.clone(),
+            settings: PlaybackSettings::LOOP.paused(),
+        },
+        WallSlideSound,
+    ));
+    commands.insert_resource(sounds);
+}
+
+fn play_sound(commands: &mut Commands, source: &Handle<AudioSource>, settings: &AudioSettings, volume: f32) {
+    let volume = settings.master * settings.sfx * volume;
+    commands.spawn((
+        AudioBundle {
+            source: source.clone(),
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(volume)),
+        },
+        SoundEffect(Timer::new(SFX_LIFETIME, TimerMode::Once)),
+    ));
+}
+
+fn jump_sound(
+    mut commands: Commands,
+    mut jump_event: EventReader<JustJumped>,
+    sounds: Res<SoundEffects>,
+    settings: Res<AudioSettings>,
+) {
+    for _ in jump_event.read() {
+        play_sound(&mut commands, &sounds.jump, &settings, 1.0);
+    }
+}
+
+fn landing_sound(
+    mut commands: Commands,
+    mut landed_event: EventReader<Landed>,
+    sounds: Res<SoundEffects>,
+    settings: Res<AudioSettings>,
+) {
+    for event in landed_event.read() {
+        // Louder with the impact, but always audible
+        let volume = (event.impact_speed / LOUD_LANDING_SPEED).clamp(0.3, 1.0);
+        play_sound(&mut commands, &sounds.land, &settings, volume);
+    }
+}
+
+fn grab_sound(
+    mut commands: Commands,
+    mut grab_event: EventReader<HoldGrab>,
+    sounds: Res<SoundEffects>,
+    settings: Res<AudioSettings>,
+) {
+    for event in grab_event.read() {
+        let source = if event.success { &sounds.grab } else { &sounds.grab_failed };
+        play_sound(&mut commands, source, &settings, 1.0);
+    }
+}
+
+fn holds_sound(
+    mut commands: Commands,
+    mut show_holds_event: EventReader<ShowHolds>,
+    sounds: Res<SoundEffects>,
+    settings: Res<AudioSettings>,
+) {
+    for event in show_holds_event.read() {
+        let source = match event.0 {
+            HoldsVisibility::Visible => &sounds.holds_shown,
+            HoldsVisibility::Hidden => &sounds.holds_hidden,
+        };
+        play_sound(&mut commands, source, &settings, 0.6);
+    }
+}
+
+fn wall_slide_sound(
+    player_query: Query<(&Player, &Velocity)>,
+    sink_query: Query<&AudioSink, With<WallSlideSound>>,
+    state: Res<State<ApplicationState>>,
+    settings: Res<AudioSettings>,
+) {
+    let Ok((player, velocity)) = player_query.get_single() else {
+        return;
+    };
+    let sliding = *state.get() == ApplicationState::InGame && is_wall_sliding(player, velocity);
+
+    for sink in sink_query.iter() {
+        sink.set_volume(settings.master * settings.sfx);
+        if sliding {
+            sink.play();
+        } else {
+            sink.pause();
+        }
+    }
+}
+
+fn remove_sound_effects(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut SoundEffect)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sound) in query.iter_mut() {
+        sound.0.tick(time.delta());
+        if sound.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Fade the current music out and the one of the new level in
+fn change_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+    mut query: Query<&mut Music>,
+) {
+    let path = LEVELS[current_level.0].music;
+    if query.iter().any(|music| music.path == path && !music.fading_out) {
+        return;
+    }
+
+    for mut music in query.iter_mut() {
+        music.fading_out = true;
+    }
+    info!("Playing music {}", path);
+    commands.spawn((
+        AudioBundle {
+            source: asset_server.load(path),
+            settings: PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+        },
+        Music { path, fade: 0.0, fading_out: false },
+    ));
+}
+
+fn fade_music(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Music, Option<&AudioSink>)>,
+    settings: Res<AudioSettings>,
+    time: Res<Time>,
+) {
+    let step = time.delta_seconds() / CROSSFADE_DURATION;
+
+    for (entity, mut music, sink) in query.iter_mut() {
+        if music.fading_out {
+            music.fade = (music.fade - step).max(0.0);
+            if music.fade == 0.0 {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        } else {
+            music.fade = (music.fade + step).min(1.0);
+        }
+
+        // No sink when there is no audio device
+        if let Some(sink) = sink {
+            sink.set_volume(music.fade * settings.master * settings.music);
+        }
+    }
+}
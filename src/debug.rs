@@ -0,0 +1,107 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Debug overlay, only built with the `debug` feature.
+/// F3 shows the overlay with the player state, the other function keys switch
+/// the debug tools listed in it.
+use bevy::prelude::*;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy_rapier2d::prelude::*;
+
+use crate::DebugGizmos;
+use crate::player::debug::player_debug_text;
+use crate::world::debug_grid;
+
+const OVERLAY_KEY: KeyCode = KeyCode::F3;
+const GIZMOS_KEY: KeyCode = KeyCode::F4;
+const PHYSICS_KEY: KeyCode = KeyCode::F5;
+const INSPECTOR_KEY: KeyCode = KeyCode::F6;
+const GRID_KEY: KeyCode = KeyCode::F7;
+
+pub struct DebugPlugin;
+
+#[derive(Resource, Default)]
+struct DebugSettings {
+    overlay: bool,
+    inspector: bool,
+    grid: bool,
+}
+
+/// Text of the overlay, the second section is filled by the player module
+#[derive(Component)]
+pub struct DebugOverlay;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugSettings>();
+        app.add_plugins(WorldInspectorPlugin::new().run_if(|settings: Res<DebugSettings>| settings.inspector));
+        app.add_plugins(RapierDebugRenderPlugin {
+            enabled: false,
+            ..default()
+        });
+        app.add_systems(Startup, overlay_setup);
+        app.add_systems(Update, toggle_debug);
+        app.add_systems(Update, player_debug_text.after(toggle_debug));
+        app.add_systems(Update, debug_grid.run_if(|settings: Res<DebugSettings>| settings.grid));
+    }
+}
+
+fn overlay_setup(mut commands: Commands) {
+    let style = TextStyle {
+        font_size: 16.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    // The tools state, then the player state
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("", style.clone()),
+            TextSection::new("", style),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        DebugOverlay,
+    ));
+}
+
+fn toggle_debug(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<DebugSettings>,
+    mut gizmos: ResMut<DebugGizmos>,
+    mut physics: ResMut<DebugRenderContext>,
+    mut query: Query<(&mut Text, &mut Visibility), With<DebugOverlay>>,
+) {
+    if keyboard_input.just_pressed(OVERLAY_KEY) {
+        settings.overlay = !settings.overlay;
+    }
+    if keyboard_input.just_pressed(GIZMOS_KEY) {
+        gizmos.0 = !gizmos.0;
+    }
+    if keyboard_input.just_pressed(PHYSICS_KEY) {
+        physics.enabled = !physics.enabled;
+    }
+    if keyboard_input.just_pressed(INSPECTOR_KEY) {
+        settings.inspector = !settings.inspector;
+    }
+    if keyboard_input.just_pressed(GRID_KEY) {
+        settings.grid = !settings.grid;
+    }
+
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+    for (mut text, mut visibility) in query.iter_mut() {
+        *visibility = if settings.overlay { Visibility::Inherited } else { Visibility::Hidden };
+        text.sections[0].value = format!(
+            "F4 sensing gizmos: {}\nF5 physics: {}\nF6 inspector: {}\nF7 grid: {}\n",
+            on_off(gizmos.0),
+            on_off(physics.enabled),
+            on_off(settings.inspector),
+            on_off(settings.grid),
+        );
+    }
+}
@@ -5,38 +5,60 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_particle_systems::ParticleSystemPlugin;
-use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
+mod audio;
+#[cfg(feature = "debug")]
+mod debug;
+mod menu;
 mod player;
+mod save;
+mod speedrun;
 mod world;
 
+use audio::SoundPlugin;
+use menu::MenuPlugin;
 use player::PlayerPlugin;
-use world::WorldPlugin;
+use save::SavePlugin;
+use speedrun::SpeedrunPlugin;
+use world::{WorldPlugin, one_way::OneWayPlatformHooks};
 
+/// Draw the sensing rays, switched at runtime by the debug overlay
 #[derive(Resource)]
-struct DebugGizmos(bool);
+pub struct DebugGizmos(pub bool);
 
-fn main() {
-    let debug_gizmos = DebugGizmos(false);
-    // let debug_gizmos = DebugGizmos(true);
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum ApplicationState {
+    #[default] Title,
+    InGame,
+    Paused,
+    LevelComplete,
+}
+
+/// Request to restart the current level from the beginning
+#[derive(Event, Default)]
+pub struct RestartEvent;
 
-    App::new()
-        .insert_resource(debug_gizmos)
+fn main() {
+    let mut app = App::new();
+    app.insert_resource(DebugGizmos(false))
         .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest())) // prevents blurry sprites
-        // .add_plugins(WorldInspectorPlugin::new())
-        .add_systems(Update, bevy::window::close_on_esc)
         // Assume that the player is 2m tall
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+        .add_plugins(RapierPhysicsPlugin::<OneWayPlatformHooks>::pixels_per_meter(
             player::SPRITE_HEIGHT / 2.0,
         ))
-        // .add_plugins(RapierDebugRenderPlugin::default())
         .add_plugins(ParticleSystemPlugin)
-        // // Custom plugin and systems
-        // .insert_state(ApplicationState::LandingScreen)
-        // .add_event::<RestartEvent>()
-        // .add_systems(Startup, menu_setup)
-        // .add_systems(Update, menu_control)
+        // Custom plugin and systems
+        .init_state::<ApplicationState>()
+        .add_event::<RestartEvent>()
+        .add_plugins(SavePlugin)
+        .add_plugins(SoundPlugin)
+        .add_plugins(MenuPlugin)
         .add_plugins(WorldPlugin)
         .add_plugins(PlayerPlugin)
-        .run();
+        .add_plugins(SpeedrunPlugin);
+
+    #[cfg(feature = "debug")]
+    app.add_plugins(debug::DebugPlugin);
+
+    app.run();
 }
@@ -0,0 +1,354 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Title screen, pause menu and level complete screen.
+/// Each screen is spawned when entering its `ApplicationState` and removed
+/// when leaving it.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{ApplicationState, RestartEvent};
+use crate::player::skins::{collected_count, next_unlocked_skin, selected_skin};
+use crate::save::SaveData;
+use crate::speedrun::stop_timer;
+use crate::world::{levels::LEVELS, finish::LevelFinished, CurrentLevel};
+
+const BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.2);
+const BUTTON_HOVERED_COLOR: Color = Color::rgb(0.25, 0.25, 0.35);
+// The volume buttons go up by this step, then back to silent
+const VOLUME_STEP: f32 = 0.25;
+
+pub struct MenuPlugin;
+
+/// Tag the root node of the menu screens
+#[derive(Component)]
+struct MenuScreen;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum MenuButton {
+    Play,
+    Level,
+    Skin,
+    CameraShake,
+    CameraZoom,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Resume,
+    Restart,
+    NextLevel,
+    Title,
+}
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(ApplicationState::Title), (title_setup, pause_physics));
+        app.add_systems(OnEnter(ApplicationState::Paused), (pause_setup, pause_physics));
+        app.add_systems(OnEnter(ApplicationState::LevelComplete), (level_complete_setup, pause_physics));
+        app.add_systems(OnEnter(ApplicationState::InGame), resume_physics);
+        app.add_systems(OnExit(ApplicationState::Title), despawn_screen);
+        app.add_systems(OnExit(ApplicationState::Paused), despawn_screen);
+        app.add_systems(OnExit(ApplicationState::LevelComplete), despawn_screen);
+        app.add_systems(Update, (menu_control, keyboard_control));
+        app.add_systems(
+            Update,
+            button_labels.run_if(resource_changed::<SaveData>.or_else(resource_changed::<CurrentLevel>)),
+        );
+        // Leave InGame only once the speedrun timer saw the finish
+        app.add_systems(Update, level_finished.after(stop_timer).run_if(in_state(ApplicationState::InGame)));
+    }
+}
+
+fn title_setup(mut commands: Commands, save: Res<SaveData>, current_level: Res<CurrentLevel>) {
+    let level = setting_text(MenuButton::Level, &save, &current_level);
+    let skin = setting_text(MenuButton::Skin, &save, &current_level);
+    let shake = setting_text(MenuButton::CameraShake, &save, &current_level);
+    let zoom = setting_text(MenuButton::CameraZoom, &save, &current_level);
+    let master = setting_text(MenuButton::MasterVolume, &save, &current_level);
+    let music = setting_text(MenuButton::MusicVolume, &save, &current_level);
+    let sfx = setting_text(MenuButton::SfxVolume, &save, &current_level);
+    spawn_screen(
+        &mut commands,
+        "pl-Art-Former",
+        &[
+            ("Play", MenuButton::Play),
+            (&level, MenuButton::Level),
+            (&skin, MenuButton::Skin),
+            (&shake, MenuButton::CameraShake),
+            (&zoom, MenuButton::CameraZoom),
+            (&master, MenuButton::MasterVolume),
+            (&music, MenuButton::MusicVolume),
+            (&sfx, MenuButton::SfxVolume),
+        ],
+    );
+}
+
+/// Label of the buttons showing the current value of a setting, empty for the
+/// other buttons
+fn setting_text(button: MenuButton, save: &SaveData, current_level: &CurrentLevel) -> String {
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+    match button {
+        MenuButton::Level => format!("Level: {}", LEVELS[current_level.0].id),
+        MenuButton::Skin => format!("Skin: {}", selected_skin(save).name),
+        MenuButton::CameraShake => format!("Camera shake: {}", on_off(save.settings.camera_shake)),
+        MenuButton::CameraZoom => format!("Camera zoom: {}", on_off(save.settings.camera_zoom)),
+        MenuButton::MasterVolume => format!("Volume: {:.0}%", save.settings.master_volume * 100.0),
+        MenuButton::MusicVolume => format!("Music: {:.0}%", save.settings.music_volume * 100.0),
+        MenuButton::SfxVolume => format!("Sound effects: {:.0}%", save.settings.sfx_volume * 100.0),
+        _ => String::new(),
+    }
+}
+
+/// Number of levels the player can select, the first one is always playable
+fn unlocked_levels(save: &SaveData) -> usize {
+    save.unlocked_levels.clamp(1, LEVELS.len())
+}
+
+fn has_next_level(current_level: &CurrentLevel, save: &SaveData) -> bool {
+    current_level.0 + 1 < unlocked_levels(save)
+}
+
+/// Show the current settings on their buttons
+fn button_labels(
+    save: Res<SaveData>,
+    current_level: Res<CurrentLevel>,
+    button_query: Query<(&MenuButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children) in button_query.iter() {
+        let label = setting_text(*button, &save, &current_level);
+        if label.is_empty() {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+fn pause_setup(mut commands: Commands, save: Res<SaveData>, current_level: Res<CurrentLevel>) {
+    let shake = setting_text(MenuButton::CameraShake, &save, &current_level);
+    let zoom = setting_text(MenuButton::CameraZoom, &save, &current_level);
+    let master = setting_text(MenuButton::MasterVolume, &save, &current_level);
+    let music = setting_text(MenuButton::MusicVolume, &save, &current_level);
+    let sfx = setting_text(MenuButton::SfxVolume, &save, &current_level);
+    spawn_screen(
+        &mut commands,
+        "Paused",
+        &[
+            ("Resume", MenuButton::Resume),
+            ("Restart", MenuButton::Restart),
+            (&shake, MenuButton::CameraShake),
+            (&zoom, MenuButton::CameraZoom),
+            (&master, MenuButton::MasterVolume),
+            (&music, MenuButton::MusicVolume),
+            (&sfx, MenuButton::SfxVolume),
+            ("Quit to title", MenuButton::Title),
+        ],
+    );
+}
+
+fn level_complete_setup(mut commands: Commands, current_level: Res<CurrentLevel>, save: Res<SaveData>) {
+    if has_next_level(&current_level, &save) {
+        spawn_screen(
+            &mut commands,
+            "Level complete!",
+            &[("Next level", MenuButton::NextLevel), ("Title", MenuButton::Title)],
+        );
+    } else {
+        spawn_screen(&mut commands, "All levels complete!", &[("Title", MenuButton::Title)]);
+    }
+}
+
+fn spawn_screen(commands: &mut Commands, title: &str, buttons: &[(&str, MenuButton)]) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(16.0),
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                // Draw over the in-game UI
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            MenuScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            for (label, action) in buttons {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(240.0),
+                                height: Val::Px(56.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        *action,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            *label,
+                            TextStyle {
+                                font_size: 24.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn despawn_screen(mut commands: Commands, query: Query<Entity, With<MenuScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn menu_control(
+    mut query: Query<(&Interaction, &MenuButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<ApplicationState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut restart_event: EventWriter<RestartEvent>,
+    mut save: ResMut<SaveData>,
+) {
+    for (interaction, button, mut color) in query.iter_mut() {
+        match interaction {
+            Interaction::Pressed => {
+                apply_menu_action(*button, &mut next_state, &mut current_level, &mut restart_event, &mut save);
+            }
+            Interaction::Hovered => *color = BUTTON_HOVERED_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
+
+fn keyboard_control(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<ApplicationState>>,
+    mut next_state: ResMut<NextState<ApplicationState>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut restart_event: EventWriter<RestartEvent>,
+    mut save: ResMut<SaveData>,
+) {
+    let action = match state.get() {
+        ApplicationState::Title if keyboard_input.just_pressed(KeyCode::Enter) => Some(MenuButton::Play),
+        ApplicationState::Title if keyboard_input.just_pressed(KeyCode::KeyL) => Some(MenuButton::Level),
+        ApplicationState::Title if keyboard_input.just_pressed(KeyCode::KeyS) => Some(MenuButton::Skin),
+        ApplicationState::InGame if keyboard_input.just_pressed(KeyCode::Escape) => {
+            next_state.set(ApplicationState::Paused);
+            None
+        }
+        ApplicationState::InGame if keyboard_input.just_pressed(KeyCode::KeyR) => Some(MenuButton::Restart),
+        ApplicationState::Paused if keyboard_input.just_pressed(KeyCode::Escape) => Some(MenuButton::Resume),
+        ApplicationState::LevelComplete if keyboard_input.just_pressed(KeyCode::Enter) => {
+            if has_next_level(&current_level, &save) {
+                Some(MenuButton::NextLevel)
+            } else {
+                Some(MenuButton::Title)
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(action) = action {
+        apply_menu_action(action, &mut next_state, &mut current_level, &mut restart_event, &mut save);
+    }
+}
+
+fn apply_menu_action(
+    action: MenuButton,
+    next_state: &mut NextState<ApplicationState>,
+    current_level: &mut ResMut<CurrentLevel>,
+    restart_event: &mut EventWriter<RestartEvent>,
+    save: &mut ResMut<SaveData>,
+) {
+    match action {
+        MenuButton::Play => {
+            // Reload the level to start from a clean state
+            current_level.set_changed();
+            next_state.set(ApplicationState::InGame);
+        }
+        MenuButton::Level => {
+            // Cycle through the unlocked levels
+            current_level.0 = (current_level.0 + 1) % unlocked_levels(save);
+        }
+        MenuButton::Skin => {
+            let collected = collected_count(save);
+            let skin = next_unlocked_skin(&save.settings.skin, collected);
+            if skin.id != save.settings.skin {
+                save.settings.skin = skin.id.to_string();
+            }
+        }
+        MenuButton::CameraShake => save.settings.camera_shake = !save.settings.camera_shake,
+        MenuButton::CameraZoom => save.settings.camera_zoom = !save.settings.camera_zoom,
+        MenuButton::MasterVolume => save.settings.master_volume = next_volume(save.settings.master_volume),
+        MenuButton::MusicVolume => save.settings.music_volume = next_volume(save.settings.music_volume),
+        MenuButton::SfxVolume => save.settings.sfx_volume = next_volume(save.settings.sfx_volume),
+        MenuButton::Resume => next_state.set(ApplicationState::InGame),
+        MenuButton::Restart => {
+            restart_event.send_default();
+            next_state.set(ApplicationState::InGame);
+        }
+        MenuButton::NextLevel => {
+            if has_next_level(current_level, save) {
+                current_level.0 += 1;
+                next_state.set(ApplicationState::InGame);
+            }
+        }
+        MenuButton::Title => next_state.set(ApplicationState::Title),
+    }
+}
+
+/// Volume of the next step, silent after the full volume
+fn next_volume(volume: f32) -> f32 {
+    if volume >= 1.0 {
+        0.0
+    } else {
+        (((volume / VOLUME_STEP).floor() + 1.0) * VOLUME_STEP).min(1.0)
+    }
+}
+
+fn level_finished(
+    mut finished_event: EventReader<LevelFinished>,
+    mut next_state: ResMut<NextState<ApplicationState>>,
+) {
+    if !finished_event.is_empty() {
+        finished_event.clear();
+        next_state.set(ApplicationState::LevelComplete);
+    }
+}
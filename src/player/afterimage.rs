@@ -0,0 +1,136 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Fading copies of the player sprite left behind when moving fast.
+/// A fixed number of afterimages is spawned at startup and reused, the oldest
+/// one is taken when they are all visible.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::*;
+
+const MAX_AFTERIMAGES: usize = 8;
+const AFTERIMAGE_INTERVAL: f32 = 0.04;
+const AFTERIMAGE_LIFETIME: f32 = 0.25;
+// Horizontal speed only reached with a dash or a bounce pad
+const FAST_SPEED: f32 = 1.2 * MAX_RUNNING_SPEED;
+// Time the trail stays after a wall jump
+const WALL_JUMP_TRAIL: f32 = 0.25;
+
+const LAUNCHED_TINT: Color = Color::rgba(1.0, 0.6, 0.2, 0.6);
+const FAST_TINT: Color = Color::rgba(0.4, 0.7, 1.0, 0.6);
+const FALLING_TINT: Color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+
+#[derive(Component)]
+pub struct Afterimage {
+    timer: Timer,
+    tint: Color,
+}
+
+/// Time before the next afterimage, and remaining trail of a wall jump
+#[derive(Default)]
+pub struct TrailState {
+    interval: f32,
+    wall_jump: f32,
+}
+
+pub fn setup(mut commands: Commands) {
+    for _ in 0..MAX_AFTERIMAGES {
+        let mut timer = Timer::from_seconds(AFTERIMAGE_LIFETIME, TimerMode::Once);
+        let duration = timer.duration();
+        timer.set_elapsed(duration);
+
+        commands.spawn((
+            SpriteSheetBundle {
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Afterimage { timer, tint: Color::NONE },
+        ));
+    }
+}
+
+/// Tint of the trail for the current movement, none when too slow
+fn trail_tint(player: &Player, velocity: &Velocity, wall_jumping: bool) -> Option<Color> {
+    if player.launched {
+        Some(LAUNCHED_TINT)
+    } else if wall_jumping || velocity.linvel.x.abs() > FAST_SPEED {
+        Some(FAST_TINT)
+    } else if velocity.linvel.y <= -MAX_FALLING_SPEED {
+        Some(FALLING_TINT)
+    } else {
+        None
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn spawn_afterimages(
+    time: Res<Time>,
+    mut jump_event: EventReader<JustJumped>,
+    player_query: Query<(&Player, &Velocity)>,
+    sprite_query: Query<(&GlobalTransform, &Handle<Image>, &TextureAtlas, &Sprite), With<PlayerSprite>>,
+    mut query: Query<
+        (&mut Afterimage, &mut Transform, &mut Handle<Image>, &mut TextureAtlas, &mut Sprite, &mut Visibility),
+        Without<PlayerSprite>,
+    >,
+    mut trail: Local<TrailState>,
+) {
+    let (player, velocity) = player_query.single();
+    let delta = time.delta_seconds();
+
+    for event in jump_event.read() {
+        if event.from_wall {
+            trail.wall_jump = WALL_JUMP_TRAIL;
+        }
+    }
+    trail.wall_jump = (trail.wall_jump - delta).max(0.0);
+    trail.interval -= delta;
+
+    let Some(tint) = trail_tint(player, velocity, trail.wall_jump > 0.0) else {
+        return;
+    };
+    if trail.interval > 0.0 {
+        return;
+    }
+    trail.interval = AFTERIMAGE_INTERVAL;
+
+    let (sprite_transform, sprite_texture, sprite_atlas, player_sprite) = sprite_query.single();
+    // A hidden afterimage, or the oldest one
+    let afterimage = query
+        .iter_mut()
+        .max_by(|(a, ..), (b, ..)| a.timer.fraction().total_cmp(&b.timer.fraction()));
+    let Some((mut afterimage, mut transform, mut texture, mut atlas, mut sprite, mut visibility)) = afterimage else {
+        return;
+    };
+
+    *transform = sprite_transform.compute_transform();
+    // Behind the player
+    transform.translation.z -= 0.1;
+    *texture = sprite_texture.clone();
+    *atlas = sprite_atlas.clone();
+    sprite.flip_x = player_sprite.flip_x;
+    sprite.color = tint;
+    *visibility = Visibility::Inherited;
+    afterimage.tint = tint;
+    afterimage.timer.reset();
+}
+
+pub fn fade_afterimages(
+    time: Res<Time>,
+    mut query: Query<(&mut Afterimage, &mut Sprite, &mut Visibility)>,
+) {
+    for (mut afterimage, mut sprite, mut visibility) in query.iter_mut() {
+        if afterimage.timer.finished() {
+            continue;
+        }
+
+        afterimage.timer.tick(time.delta());
+        if afterimage.timer.finished() {
+            *visibility = Visibility::Hidden;
+        } else {
+            let alpha = afterimage.tint.a() * (1.0 - afterimage.timer.fraction());
+            sprite.color = afterimage.tint.with_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,456 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Sprite animations described in a RON asset, so the sprite-sheet can change
+/// without touching the code.
+/// The asset lists named clips of the sprite-sheet, the clip to play in each
+/// `PlayerState`, the clips played once when going from a state to another,
+/// and the clips of the actions triggered by the gameplay.
+use std::collections::HashMap;
+use std::fmt;
+
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::BoxedFuture;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::player::*;
+use crate::player::controller::{Action, Controller};
+use crate::player::movement::{HoldGrab, MAX_RUNNING_SPEED, is_wall_sliding};
+use crate::player::sensing::Landed;
+
+pub const PLAYER_ANIMATIONS: &str = "animations/player.anim.ron";
+
+// Used by clips without any duration
+const DEFAULT_FRAME_DURATION: f32 = 0.1;
+// Minimal speed to skid when turning around
+const SKID_SPEED: f32 = MAX_RUNNING_SPEED / 2.0;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum AnimationMode {
+    /// Start again from the first frame after the last one
+    #[default]
+    Loop,
+    /// Stop on the last frame
+    Once,
+    /// Go back and forth between the first and last frames
+    PingPong,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimationClip {
+    /// Indices of the first and last frames in the sprite-sheet
+    pub first: usize,
+    pub last: usize,
+    /// Duration of each frame in seconds, the last one applies to the
+    /// remaining frames
+    #[serde(default)]
+    pub durations: Vec<f32>,
+    #[serde(default)]
+    pub mode: AnimationMode,
+}
+
+impl AnimationClip {
+    pub fn frame_count(&self) -> usize {
+        self.last.saturating_sub(self.first) + 1
+    }
+
+    /// Duration of a frame, counted from the start of the clip
+    pub fn frame_duration(&self, frame: usize) -> f32 {
+        self.durations
+            .get(frame)
+            .or(self.durations.last())
+            .copied()
+            .unwrap_or(DEFAULT_FRAME_DURATION)
+    }
+
+    /// Frames in playing order for one cycle of the clip
+    fn sequence(&self) -> impl Iterator<Item = usize> {
+        let count = self.frame_count();
+        let back = if self.mode == AnimationMode::PingPong && count > 2 {
+            1..count - 1
+        } else {
+            0..0
+        };
+        (0..count).chain(back.rev())
+    }
+
+    /// Duration of one cycle of the clip
+    pub fn duration(&self) -> f32 {
+        self.sequence().map(|frame| self.frame_duration(frame)).sum()
+    }
+
+    /// A clip played once is over after its last frame, the others never end
+    pub fn is_finished(&self, elapsed: f32) -> bool {
+        self.mode == AnimationMode::Once && elapsed >= self.duration()
+    }
+
+    /// Index in the sprite-sheet to display `elapsed` seconds after the start
+    pub fn frame_at(&self, elapsed: f32) -> usize {
+        let duration = self.duration();
+        if self.frame_count() == 1 || duration <= 0.0 {
+            return self.first;
+        }
+        if self.is_finished(elapsed) {
+            return self.last;
+        }
+
+        let mut time = elapsed.max(0.0) % duration;
+        for frame in self.sequence() {
+            let frame_duration = self.frame_duration(frame);
+            if time < frame_duration {
+                return self.first + frame;
+            }
+            time -= frame_duration;
+        }
+        self.first
+    }
+}
+
+/// Animations started by the gameplay rather than by a state change
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationAction {
+    /// Played once when touching the ground
+    Land,
+    /// Played once when turning around while running
+    Skid,
+    /// Replace the state clip while sliding down a wall
+    WallSlide,
+    /// Played once when grabbing a hold in the given direction
+    ClimbUp,
+    ClimbDown,
+    ClimbLeft,
+    ClimbRight,
+}
+
+#[derive(Deserialize, Debug)]
+struct AnimationTransition {
+    from: PlayerState,
+    to: PlayerState,
+    clip: String,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct AnimationSet {
+    clips: HashMap<String, AnimationClip>,
+    /// Clip played in each state
+    states: HashMap<PlayerState, String>,
+    #[serde(default)]
+    transitions: Vec<AnimationTransition>,
+    #[serde(default)]
+    actions: HashMap<AnimationAction, String>,
+}
+
+impl AnimationSet {
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    pub(super) fn state_clip(&self, state: PlayerState) -> Option<&str> {
+        self.states.get(&state).map(String::as_str)
+    }
+
+    /// Clip to play once before the one of the `to` state
+    pub(super) fn transition_clip(&self, from: PlayerState, to: PlayerState) -> Option<&str> {
+        self.transitions
+            .iter()
+            .find(|transition| transition.from == from && transition.to == to)
+            .map(|transition| transition.clip.as_str())
+    }
+
+    pub fn action_clip(&self, action: AnimationAction) -> Option<&str> {
+        self.actions.get(&action).map(String::as_str)
+    }
+}
+
+#[derive(Default)]
+pub struct AnimationSetLoader;
+
+#[derive(Debug)]
+pub enum AnimationSetLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for AnimationSetLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnimationSetLoaderError::Io(error) => write!(f, "could not read the animations: {}", error),
+            AnimationSetLoaderError::Ron(error) => write!(f, "could not parse the animations: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for AnimationSetLoaderError {}
+
+impl From<std::io::Error> for AnimationSetLoaderError {
+    fn from(error: std::io::Error) -> Self {
+        AnimationSetLoaderError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for AnimationSetLoaderError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        AnimationSetLoaderError::Ron(error)
+    }
+}
+
+impl AssetLoader for AnimationSetLoader {
+    type Asset = AnimationSet;
+    type Settings = ();
+    type Error = AnimationSetLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<AnimationSet, AnimationSetLoaderError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes::<AnimationSet>(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
+
+/// Clip currently played by a sprite
+#[derive(Component)]
+pub struct SpriteAnimation {
+    pub set: Handle<AnimationSet>,
+    /// Action to play once, from the next update
+    pub triggered: Option<AnimationAction>,
+    /// Action replacing the state clip while set
+    pub held: Option<AnimationAction>,
+    clip: String,
+    /// Clip to play once the current one is finished
+    next_clip: Option<String>,
+    state: Option<PlayerState>,
+    elapsed: f32,
+}
+
+impl SpriteAnimation {
+    pub fn new(set: Handle<AnimationSet>) -> Self {
+        SpriteAnimation {
+            set,
+            triggered: None,
+            held: None,
+            clip: String::new(),
+            next_clip: None,
+            state: None,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Play `clip` from its start, then `next_clip` if any
+    fn start(&mut self, clip: &str, next_clip: Option<String>) {
+        self.clip = clip.to_string();
+        self.next_clip = next_clip;
+        self.elapsed = 0.0;
+    }
+}
+
+/// Start the action animations from the gameplay events
+pub fn trigger_animations(
+    mut landed_event: EventReader<Landed>,
+    mut grab_event: EventReader<HoldGrab>,
+    query: Query<(&Player, &Controller, &Velocity)>,
+    mut animation_query: Query<&mut SpriteAnimation, With<PlayerSprite>>,
+    mut skidding: Local<bool>,
+) {
+    let (player, controller, velocity) = query.single();
+    let mut animation = animation_query.single_mut();
+
+    if !landed_event.is_empty() {
+        landed_event.clear();
+        animation.triggered = Some(AnimationAction::Land);
+    }
+
+    for event in grab_event.read() {
+        animation.triggered = match event.action {
+            Action::GrabUp => Some(AnimationAction::ClimbUp),
+            Action::GrabDown => Some(AnimationAction::ClimbDown),
+            Action::GrabLeft => Some(AnimationAction::ClimbLeft),
+            Action::GrabRight => Some(AnimationAction::ClimbRight),
+            _ => animation.triggered,
+        };
+    }
+
+    // Running against the current velocity
+    let skid = player.state == PlayerState::Running
+        && controller.direction.x * velocity.linvel.x < 0.0
+        && velocity.linvel.x.abs() > SKID_SPEED;
+    if skid && !*skidding {
+        animation.triggered = Some(AnimationAction::Skid);
+    }
+    *skidding = skid;
+
+    let sliding = is_wall_sliding(player, velocity);
+    animation.held = sliding.then_some(AnimationAction::WallSlide);
+}
+
+pub fn animate_sprite(
+    time: Res<Time>,
+    sets: Res<Assets<AnimationSet>>,
+    player_query: Query<&Player>,
+    mut query: Query<(&Parent, &mut SpriteAnimation, &mut TextureAtlas)>,
+) {
+    for (parent, mut animation, mut atlas) in query.iter_mut() {
+        let Ok(player) = player_query.get(parent.get()) else {
+            continue;
+        };
+        // Not loaded yet
+        let Some(set) = sets.get(&animation.set) else {
+            continue;
+        };
+
+        let base_clip = animation
+            .held
+            .and_then(|action| set.action_clip(action))
+            .or_else(|| set.state_clip(player.state))
+            .unwrap_or_default()
+            .to_string();
+        let previous_state = animation.state.replace(player.state);
+
+        if let Some(action_clip) = animation.triggered.take().and_then(|action| set.action_clip(action)) {
+            animation.start(action_clip, Some(base_clip));
+        } else if previous_state != Some(player.state) {
+            match previous_state.and_then(|from| set.transition_clip(from, player.state)) {
+                Some(transition_clip) => animation.start(transition_clip, Some(base_clip)),
+                // Finish the action clip, the landing often comes before the state change
+                None if animation.next_clip.is_some() => {
+                    animation.next_clip = Some(base_clip);
+                    animation.elapsed += time.delta_seconds();
+                }
+                None => animation.start(&base_clip, None),
+            }
+        } else if animation.next_clip.is_some() {
+            // Follow the changes of the held action during a clip played once
+            animation.next_clip = Some(base_clip);
+            animation.elapsed += time.delta_seconds();
+        } else if animation.clip != base_clip {
+            animation.start(&base_clip, None);
+        } else {
+            animation.elapsed += time.delta_seconds();
+        }
+
+        let Some(mut clip) = set.clip(&animation.clip) else {
+            continue;
+        };
+        if clip.is_finished(animation.elapsed) {
+            if let Some(next_clip) = animation.next_clip.take() {
+                if let Some(next) = set.clip(&next_clip) {
+                    clip = next;
+                }
+                animation.start(&next_clip, None);
+            }
+        }
+
+        atlas.index = clip.frame_at(animation.elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(first: usize, last: usize, durations: &[f32], mode: AnimationMode) -> AnimationClip {
+        AnimationClip {
+            first,
+            last,
+            durations: durations.to_vec(),
+            mode,
+        }
+    }
+
+    #[test]
+    fn loop_starts_again_after_the_last_frame() {
+        let clip = clip(4, 6, &[0.1], AnimationMode::Loop);
+        assert_eq!(clip.sequence().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(clip.frame_at(0.0), 4);
+        assert_eq!(clip.frame_at(0.15), 5);
+        assert_eq!(clip.frame_at(0.25), 6);
+        assert_eq!(clip.frame_at(0.35), 4);
+        assert!(!clip.is_finished(10.0));
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let clip = clip(10, 12, &[0.1], AnimationMode::Once);
+        assert_eq!(clip.frame_at(0.05), 10);
+        assert_eq!(clip.frame_at(0.25), 12);
+        assert!(!clip.is_finished(0.25));
+        assert!(clip.is_finished(0.3));
+        assert_eq!(clip.frame_at(5.0), 12);
+    }
+
+    #[test]
+    fn ping_pong_goes_back_without_repeating_the_ends() {
+        let clip = clip(0, 3, &[0.1], AnimationMode::PingPong);
+        assert_eq!(clip.sequence().collect::<Vec<_>>(), vec![0, 1, 2, 3, 2, 1]);
+        assert_eq!(clip.frame_at(0.35), 3);
+        assert_eq!(clip.frame_at(0.45), 2);
+        assert_eq!(clip.frame_at(0.55), 1);
+        assert_eq!(clip.frame_at(0.65), 0);
+        assert!(!clip.is_finished(10.0));
+    }
+
+    #[test]
+    fn ping_pong_with_two_frames_or_less_is_a_loop() {
+        let two = clip(0, 1, &[0.1], AnimationMode::PingPong);
+        assert_eq!(two.sequence().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(two.frame_at(0.15), 1);
+        assert_eq!(two.frame_at(0.25), 0);
+
+        let one = clip(5, 5, &[0.1], AnimationMode::PingPong);
+        assert_eq!(one.sequence().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(one.frame_at(0.75), 5);
+    }
+
+    #[test]
+    fn frames_have_their_own_duration() {
+        let clip = clip(0, 2, &[0.1, 0.3, 0.05], AnimationMode::Loop);
+        assert!((clip.duration() - 0.45).abs() < 1e-6);
+        assert_eq!(clip.frame_at(0.05), 0);
+        assert_eq!(clip.frame_at(0.2), 1);
+        assert_eq!(clip.frame_at(0.39), 1);
+        assert_eq!(clip.frame_at(0.42), 2);
+    }
+
+    #[test]
+    fn last_duration_applies_to_the_remaining_frames() {
+        let clip = clip(0, 3, &[0.2, 0.1], AnimationMode::Once);
+        assert_eq!(clip.frame_duration(0), 0.2);
+        assert_eq!(clip.frame_duration(3), 0.1);
+        assert!((clip.duration() - 0.5).abs() < 1e-6);
+        assert_eq!(clip.frame_at(0.35), 2);
+        assert_eq!(clip.frame_at(0.45), 3);
+
+        let no_duration = AnimationClip { durations: Vec::new(), ..clip };
+        assert_eq!(no_duration.frame_duration(1), DEFAULT_FRAME_DURATION);
+    }
+
+    #[test]
+    fn transition_clip_matches_both_states() {
+        let set = AnimationSet {
+            clips: HashMap::new(),
+            states: HashMap::new(),
+            transitions: vec![AnimationTransition {
+                from: PlayerState::OnEdge,
+                to: PlayerState::InAir,
+                clip: "pull_up".to_string(),
+            }],
+            actions: HashMap::new(),
+        };
+        assert_eq!(set.transition_clip(PlayerState::OnEdge, PlayerState::InAir), Some("pull_up"));
+        assert_eq!(set.transition_clip(PlayerState::InAir, PlayerState::OnEdge), None);
+        assert_eq!(set.transition_clip(PlayerState::OnEdge, PlayerState::Idle), None);
+    }
+}
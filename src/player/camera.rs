@@ -2,16 +2,112 @@
  * Copyright (c) 2024 Louis Mayencourt
  */
 
+/// Camera following the player, with a dead zone, look-ahead and clamping
+/// to the current level bounds.
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
 
 use crate::player::*;
+use crate::player::movement::MAX_RUNNING_SPEED;
+use crate::world::LevelBounds;
+
+/// Configure how the camera follows the player
+#[derive(Component)]
+pub struct CameraRig {
+    /// Size of the area around the focus point where the player can move
+    /// without moving the camera
+    pub dead_zone: Vec2,
+    /// Distance the camera looks ahead in the facing direction, at full speed
+    pub look_ahead: f32,
+    /// Rate of the exponential smoothing, higher is snappier
+    pub smoothing: f32,
+    /// Vertical offset applied while on a wall, to see where to climb
+    pub climbing_offset: f32,
+    focus: Option<Vec2>,
+    position: Option<Vec2>,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        CameraRig {
+            dead_zone: Vec2::new(64.0, 96.0),
+            look_ahead: 96.0,
+            smoothing: 6.0,
+            climbing_offset: 64.0,
+            focus: None,
+            position: None,
+        }
+    }
+}
+
+impl CameraRig {
+    /// Jump directly to the player on the next update, without smoothing
+    pub fn snap(&mut self) {
+        self.focus = None;
+        self.position = None;
+    }
+}
 
 pub fn follow_player(
-    player_query: Query<&Transform, With<Player>>,
-    mut camera_query: Query<&mut Transform, (With<Camera>, Without<Player>)>,
+    player_query: Query<(&Transform, &Velocity, &Player)>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection, &mut CameraRig), Without<Player>>,
+    bounds: Option<Res<LevelBounds>>,
+    time: Res<Time>,
 ) {
-    let player_transform = player_query.single();
-    let mut camera_transform = camera_query.single_mut();
+    let (player_transform, velocity, player) = player_query.single();
+    let (mut camera_transform, projection, mut rig) = camera_query.single_mut();
+
+    let player_position = player_transform.translation.truncate();
+
+    // Move the focus point only when the player leaves the dead zone
+    let mut focus = rig.focus.unwrap_or(player_position);
+    let half_dead_zone = rig.dead_zone / 2.0;
+    focus = focus.clamp(player_position - half_dead_zone, player_position + half_dead_zone);
+    rig.focus = Some(focus);
+
+    let mut target = focus;
+
+    // Look ahead in the facing direction, proportionally to the speed
+    let speed_ratio = (velocity.linvel.x.abs() / MAX_RUNNING_SPEED).clamp(0.0, 1.0);
+    target.x += player.facing_direction.x * rig.look_ahead * speed_ratio;
+
+    // Frame higher on walls, to show the holds above the player
+    match player.state {
+        PlayerState::OnWall | PlayerState::OnEdge | PlayerState::Climbing => {
+            target.y += rig.climbing_offset;
+        }
+        _ => {}
+    }
+
+    // Exponential smoothing, independent of the frame rate.
+    // Use the last rig position, as the transform can hold a shake offset.
+    let current = rig.position.unwrap_or(target);
+    let t = 1.0 - (-rig.smoothing * time.delta_seconds()).exp();
+    let mut position = current.lerp(target, t);
+
+    // Don't show the void outside of the level
+    if let Some(bounds) = bounds {
+        position = clamp_to_bounds(position, projection.area.half_size(), bounds.0);
+    }
+
+    rig.position = Some(position);
+    camera_transform.translation.x = position.x;
+    camera_transform.translation.y = position.y;
+}
+
+/// Keep the view inside the bounds, or centered when the level is smaller
+/// than the view.
+fn clamp_to_bounds(position: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let clamp_axis = |value: f32, half: f32, min: f32, max: f32| {
+        if max - min < half * 2.0 {
+            (min + max) / 2.0
+        } else {
+            value.clamp(min + half, max - half)
+        }
+    };
 
-    camera_transform.translation = player_transform.translation;
-}
\ No newline at end of file
+    Vec2::new(
+        clamp_axis(position.x, half_view.x, bounds.min.x, bounds.max.x),
+        clamp_axis(position.y, half_view.y, bounds.min.y, bounds.max.y),
+    )
+}
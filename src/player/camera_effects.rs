@@ -0,0 +1,105 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Screen shake and zoom pulses applied on top of the camera rig, triggered
+/// by the player events.
+/// Shake uses the "trauma" model: events add trauma, the offset grows with
+/// its square and the trauma decays over time.
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::player::movement::*;
+use crate::player::sensing::Landed;
+
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+const TRAUMA_DECAY: f32 = 1.5;
+const ZOOM_PULSE_DECAY: f32 = 4.0;
+const MAX_ZOOM_PULSE: f32 = 0.1;
+
+const JUMP_ZOOM_PULSE: f32 = 0.3;
+const LOST_GRIP_TRAUMA: f32 = 0.3;
+// Vertical speed from which a landing shakes the screen
+const HARD_LANDING_SPEED: f32 = 400.0;
+
+/// Allow disabling the effects, for players sensitive to screen motion
+#[derive(Resource)]
+pub struct CameraEffectsSettings {
+    pub shake: bool,
+    pub zoom: bool,
+}
+
+impl Default for CameraEffectsSettings {
+    fn default() -> Self {
+        CameraEffectsSettings {
+            shake: true,
+            zoom: true,
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct CameraEffects {
+    trauma: f32,
+    zoom_pulse: f32,
+}
+
+impl CameraEffects {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    pub fn add_zoom_pulse(&mut self, amount: f32) {
+        self.zoom_pulse = (self.zoom_pulse + amount).min(1.0);
+    }
+}
+
+pub fn trigger_camera_effects(
+    mut query: Query<&mut CameraEffects>,
+    mut jump_event: EventReader<JustJumped>,
+    mut landed_event: EventReader<Landed>,
+    mut lost_grip_event: EventReader<LostGrip>,
+) {
+    let mut effects = query.single_mut();
+
+    for _ in jump_event.read() {
+        effects.add_zoom_pulse(JUMP_ZOOM_PULSE);
+    }
+
+    for event in landed_event.read() {
+        if event.impact_speed > HARD_LANDING_SPEED {
+            let impact = (event.impact_speed - HARD_LANDING_SPEED) / HARD_LANDING_SPEED;
+            effects.add_trauma(0.3 + impact);
+            effects.add_zoom_pulse(impact);
+        }
+    }
+
+    for _ in lost_grip_event.read() {
+        effects.add_trauma(LOST_GRIP_TRAUMA);
+    }
+}
+
+pub fn apply_camera_effects(
+    mut query: Query<(&mut CameraEffects, &mut Transform, &mut OrthographicProjection)>,
+    settings: Res<CameraEffectsSettings>,
+    time: Res<Time>,
+) {
+    let (mut effects, mut transform, mut projection) = query.single_mut();
+    let mut rng = rand::thread_rng();
+
+    if settings.shake && effects.trauma > 0.0 {
+        let shake = effects.trauma * effects.trauma;
+        transform.translation.x += MAX_SHAKE_OFFSET * shake * rng.gen_range(-1.0..=1.0);
+        transform.translation.y += MAX_SHAKE_OFFSET * shake * rng.gen_range(-1.0..=1.0);
+    }
+
+    if settings.zoom {
+        projection.scale = 1.0 - MAX_ZOOM_PULSE * effects.zoom_pulse;
+    } else {
+        projection.scale = 1.0;
+    }
+
+    let delta = time.delta_seconds();
+    effects.trauma = (effects.trauma - TRAUMA_DECAY * delta).max(0.0);
+    effects.zoom_pulse = (effects.zoom_pulse - ZOOM_PULSE_DECAY * delta).max(0.0);
+}
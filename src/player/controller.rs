@@ -6,6 +6,7 @@
 /// Abstract the controls from the input device, to allow playing the game
 /// with a keyboard or a game-controller.
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum Action {
@@ -17,6 +18,7 @@ pub enum Action {
     GrabRight,
     GrabUp,
     GrabDown,
+    DropDown,
 }
 
 const JUMP_MASK: u32 = 1;
@@ -39,9 +41,61 @@ pub struct Controller {
     pub action_vector: u32,
 }
 
+/// State of the on-screen buttons, set by the touch controls
+#[derive(Resource, Default, Debug)]
+pub struct VirtualButtons {
+    pub left: bool,
+    pub right: bool,
+    pub down: bool,
+    pub jump: bool,
+    pub climb: bool,
+    pub grab_left: bool,
+    pub grab_right: bool,
+    pub grab_up: bool,
+    pub grab_down: bool,
+}
+
+/// Keys assigned to each input, several keys can trigger the same input
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct InputBindings {
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    pub jump: Vec<KeyCode>,
+    pub climb: Vec<KeyCode>,
+    pub grab_left: Vec<KeyCode>,
+    pub grab_right: Vec<KeyCode>,
+    pub grab_up: Vec<KeyCode>,
+    pub grab_down: Vec<KeyCode>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        InputBindings {
+            left: vec![KeyCode::ArrowLeft],
+            right: vec![KeyCode::ArrowRight],
+            down: vec![KeyCode::ArrowDown],
+            jump: vec![KeyCode::Space],
+            climb: vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+            grab_left: vec![KeyCode::KeyA],
+            grab_right: vec![KeyCode::KeyD],
+            grab_up: vec![KeyCode::KeyW],
+            grab_down: vec![KeyCode::KeyS],
+        }
+    }
+}
+
+fn any_pressed(keyboard_input: &ButtonInput<KeyCode>, keys: &[KeyCode]) -> bool {
+    keys.iter().any(|key| keyboard_input.pressed(*key))
+}
+
 /// Controller implementation for keyboard
+/// The on-screen touch buttons are merged here, so both inputs behave the same.
 pub fn keyboard_inputs(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<InputBindings>,
+    virtual_buttons: Res<VirtualButtons>,
     mut query: Query<&mut Controller>,
     mut event: EventWriter<ActionEvent>,
     time: Res<Time>,
@@ -52,14 +106,23 @@ pub fn keyboard_inputs(
     controller.direction = Vec2::ZERO;
 
     // Directional inputs
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
+    if any_pressed(&keyboard_input, &bindings.left) || virtual_buttons.left {
         controller.direction = Vec2::NEG_X;
-    } else if keyboard_input.pressed(KeyCode::ArrowRight) {
+    } else if any_pressed(&keyboard_input, &bindings.right) || virtual_buttons.right {
         controller.direction = Vec2::X;
     }
 
     // Jump inputs
-    if keyboard_input.pressed(KeyCode::Space) {
+    let jump_pressed = any_pressed(&keyboard_input, &bindings.jump) || virtual_buttons.jump;
+    let down_pressed = any_pressed(&keyboard_input, &bindings.down) || virtual_buttons.down;
+    if jump_pressed && down_pressed {
+        // Down and jump drops through one-way platforms
+        controller.action = Action::DropDown;
+        if controller.jump_released {
+            event.send(ActionEvent(Action::DropDown));
+            controller.jump_released = false;
+        }
+    } else if jump_pressed {
         controller.direction += Vec2::Y;
         controller.action = Action::Jump;
         if controller.jump_released {
@@ -69,18 +132,20 @@ pub fn keyboard_inputs(
     }
 
     // Allow jumping when key is released.
-    if controller.previous_action == Action::Jump && !keyboard_input.pressed(KeyCode::Space) {
+    let jumping = controller.previous_action == Action::Jump || controller.previous_action == Action::DropDown;
+    if jumping && !jump_pressed {
         controller.jump_released = true;
     }
 
     // Climbing inputs
     let climb_mode = (controller.action_vector & CLIMBING_MODE_MASK) != 0;
-    if keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight) {
+    let climb_pressed = any_pressed(&keyboard_input, &bindings.climb) || virtual_buttons.climb;
+    if climb_pressed {
         if !climb_mode {
             event.send(ActionEvent(Action::EnterClimbingMode));
             controller.action_vector |= CLIMBING_MODE_MASK;
         }
-    } else if !keyboard_input.pressed(KeyCode::ShiftLeft) && !keyboard_input.pressed(KeyCode::ShiftRight){
+    } else {
         if climb_mode {
             event.send(ActionEvent(Action::ExitClimbingMode));
             controller.action_vector &= !CLIMBING_MODE_MASK;
@@ -88,16 +153,16 @@ pub fn keyboard_inputs(
     }
 
     if climb_mode {
-        if handle_key_input(&mut controller, GRAB_LEFT_MASK, keyboard_input.pressed(KeyCode::KeyA)) {
+        if handle_key_input(&mut controller, GRAB_LEFT_MASK, any_pressed(&keyboard_input, &bindings.grab_left) || virtual_buttons.grab_left) {
             event.send(ActionEvent(Action::GrabLeft));
         }
-        if handle_key_input(&mut controller, GRAB_RIGHT_MASK, keyboard_input.pressed(KeyCode::KeyD)) {
+        if handle_key_input(&mut controller, GRAB_RIGHT_MASK, any_pressed(&keyboard_input, &bindings.grab_right) || virtual_buttons.grab_right) {
             event.send(ActionEvent(Action::GrabRight));
         }
-        if handle_key_input(&mut controller, GRAB_UP_MASK, keyboard_input.pressed(KeyCode::KeyW)) {
+        if handle_key_input(&mut controller, GRAB_UP_MASK, any_pressed(&keyboard_input, &bindings.grab_up) || virtual_buttons.grab_up) {
             event.send(ActionEvent(Action::GrabUp));
         }
-        if handle_key_input(&mut controller, GRAB_DOWN_MASK, keyboard_input.pressed(KeyCode::KeyS)) {
+        if handle_key_input(&mut controller, GRAB_DOWN_MASK, any_pressed(&keyboard_input, &bindings.grab_down) || virtual_buttons.grab_down) {
             event.send(ActionEvent(Action::GrabDown));
         }
     }
@@ -0,0 +1,60 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Player part of the debug overlay, only built with the `debug` feature
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::debug::DebugOverlay;
+use crate::player::*;
+
+#[allow(clippy::type_complexity)]
+pub fn player_debug_text(
+    query: Query<
+        (&Player, &Grounded, &OnWall, &EdgeGrab, &Support, &Velocity, &InhibitionTimer, &CoyoteTimer, &DropThrough),
+    >,
+    buffered_jump: Res<BufferedJump>,
+    mut overlay_query: Query<(&mut Text, &Visibility), With<DebugOverlay>>,
+) {
+    let Ok((player, grounded, on_wall, edge_grab, support, velocity, inhibition, coyote, drop_through)) =
+        query.get_single()
+    else {
+        return;
+    };
+
+    for (mut text, visibility) in overlay_query.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        text.sections[1].value = format!(
+            "\nstate: {:?} (from {:?})\n\
+             grounded: {}  on wall: {}  edge grab: {}\n\
+             ground: {:?} {:?}\n\
+             wall: {:?} {:?}\n\
+             velocity: {:.0} {:.0}\n\
+             jumps: {}  can jump: {}  launched: {}\n\
+             inhibition: {:.2}s  coyote: {:.2}s\n\
+             buffered jump: {} {:.2}s  drop through: {:.2}s",
+            player.state,
+            player.previous_state,
+            grounded.0,
+            on_wall.0,
+            edge_grab.0,
+            support.ground,
+            support.ground_surface,
+            support.wall,
+            support.wall_surface,
+            velocity.linvel.x,
+            velocity.linvel.y,
+            player.jump_count,
+            player.can_jump,
+            player.launched,
+            inhibition.remaining_secs(),
+            coyote.remaining_secs(),
+            buffered_jump.should_jump,
+            buffered_jump.timer.remaining_secs(),
+            drop_through.remaining_secs(),
+        );
+    }
+}
@@ -4,18 +4,37 @@
 
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
 
+pub mod afterimage;
+pub mod animation;
 pub mod camera;
+pub mod camera_effects;
 pub mod controller;
+#[cfg(feature = "debug")]
+pub mod debug;
 pub mod movement;
+pub mod particles;
 pub mod sprites;
 pub mod sensing;
+pub mod skins;
+pub mod squash;
+pub mod touch;
 
+use animation::*;
 use camera::*;
+use camera_effects::*;
 use controller::*;
 use movement::*;
-use sprites::*;
+use particles::*;
 use sensing::*;
+use skins::*;
+use squash::*;
+
+use crate::{ApplicationState, RestartEvent};
+use crate::world::{CurrentLevel, SpawnPoint, hazards::PlayerDied, holds::{ShowHolds, HoldsVisibility}};
+use crate::world::one_way::DropThrough;
+use crate::world::surfaces::Surface;
 
 pub const SPRITE_HEIGHT: f32 = 16.0;
 pub const SPRITE_WIDTH: f32 = 16.0;
@@ -32,9 +51,26 @@ pub struct Player {
     facing_direction: Vec2,
     jump_count: u32,
     can_jump: bool,
+    /// Thrown by a spring or a bounce pad, until the top of the trajectory
+    launched: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl Player {
+    fn reset(&mut self) {
+        self.state = PlayerState::Idle;
+        self.previous_state = PlayerState::Idle;
+        self.jump_count = 0;
+        self.can_jump = true;
+        self.launched = false;
+    }
+}
+
+/// Sprite of the player, a child of the physics entity so it can be deformed
+/// without changing the collider
+#[derive(Component)]
+pub struct PlayerSprite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 enum PlayerState {
     Idle,
     Walking,
@@ -54,38 +90,108 @@ pub struct OnWall(bool);
 #[derive(Component)]
 pub struct EdgeGrab(bool);
 
+/// Entities detected under the player and in front of it
+#[derive(Component, Default)]
+pub struct Support {
+    pub ground: Option<Entity>,
+    pub wall: Option<Entity>,
+    /// Material of the ground and wall entities, `Normal` when there is none
+    pub ground_surface: Surface,
+    pub wall_surface: Surface,
+    /// Velocity of the support during the last update
+    pub carried: Vec2,
+}
+
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
+        app.init_asset::<AnimationSet>();
+        app.init_asset_loader::<AnimationSetLoader>();
         app.add_event::<JustJumped>();
         app.add_event::<CoyoteStart>();
         app.add_event::<ActionEvent>();
+        app.add_event::<LostGrip>();
+        app.add_event::<HoldGrab>();
+        app.add_event::<Landed>();
+        app.add_event::<EmitParticles>();
+        app.init_resource::<CameraEffectsSettings>();
+        app.init_resource::<InputBindings>();
+        app.init_resource::<VirtualButtons>();
         app.insert_resource(CoyoteJumpedFrom{jumped_from: JumpedFrom::Ground});
         app.insert_resource(BufferedJump {
             should_jump: false,
             timer: Timer::from_seconds(0.1, TimerMode::Once),
         });
         app.add_systems(Startup, setup);
-        app.add_systems(Startup, sprites::setup);
-        // app.add_systems(Update, restart_event_handler);
+        app.add_systems(Startup, particles::setup);
+        app.add_systems(Startup, afterimage::setup);
+        app.add_systems(Startup, skins::setup);
+        app.add_systems(Startup, touch::setup);
+        app.add_systems(Update, respawn_player.run_if(in_state(ApplicationState::InGame)));
+        app.add_systems(Update, movement::launch_player.run_if(in_state(ApplicationState::InGame)));
         app.add_systems(
             FixedUpdate,
-            controller::keyboard_inputs, //.run_if.(in_state(ApplicationState::InGame)),
+            controller::keyboard_inputs.run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(FixedUpdate, touch::touch_inputs.before(controller::keyboard_inputs));
+        app.add_systems(Update, touch::show_touch_controls);
+        app.add_systems(
+            FixedUpdate,
+            (
+                sensing::facing_direction,
+                sensing::ground_detection,
+                sensing::wall_detection,
+                sensing::edge_grab_detection,
+            )
+                .before(player_movement)
+                .run_if(in_state(ApplicationState::InGame)),
         );
-        app.add_systems(FixedUpdate, sensing::facing_direction.before(player_movement));
-        app.add_systems(FixedUpdate, sensing::ground_detection.before(player_movement));
-        app.add_systems(FixedUpdate, sensing::wall_detection.before(player_movement));
-        app.add_systems(FixedUpdate, sensing::edge_grab_detection.before(player_movement));
         app.add_systems(
             FixedUpdate,
             movement::player_movement
-                .after(controller::keyboard_inputs),
-                                       //.run_if(in_state(ApplicationState::InGame)),
+                .after(controller::keyboard_inputs)
+                .run_if(in_state(ApplicationState::InGame)),
         );
-        app.add_systems(FixedUpdate, movement::coyote_jump.after(controller::keyboard_inputs));
-        app.add_systems(Update, sprites::animate_sprite.after(player_movement));
+        app.add_systems(
+            FixedUpdate,
+            movement::coyote_jump
+                .after(controller::keyboard_inputs)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(Update, animation::trigger_animations.after(player_movement));
+        app.add_systems(Update, animation::animate_sprite.after(animation::trigger_animations));
         app.add_systems(Update, sprites::animate_direction.after(player_movement));
-        app.add_systems(Update, sprites::jump_particules);
+        app.add_systems(Update, (skins::build_palettes, skins::apply_skin).chain());
+        app.add_systems(Update, squash::trigger_squash_stretch.after(player_movement));
+        app.add_systems(Update, squash::animate_squash_stretch.after(squash::trigger_squash_stretch));
+        app.add_systems(
+            Update,
+            particles::trigger_particles
+                .after(player_movement)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(
+            Update,
+            afterimage::spawn_afterimages
+                .after(animation::animate_sprite)
+                .after(squash::animate_squash_stretch)
+                .after(sprites::animate_direction)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(Update, afterimage::fade_afterimages.after(afterimage::spawn_afterimages));
+        app.add_systems(
+            Update,
+            particles::play_emitters
+                .after(particles::trigger_particles)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
         app.add_systems(Update, camera::follow_player);
+        app.add_systems(Update, camera_effects::trigger_camera_effects);
+        app.add_systems(
+            Update,
+            camera_effects::apply_camera_effects
+                .after(camera_effects::trigger_camera_effects)
+                .after(camera::follow_player),
+        );
     }
 }
 
@@ -94,30 +200,22 @@ fn setup(
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    let texture = asset_server.load("tileset.png");
+    let texture = asset_server.load(PLAYER_SHEET);
     let layout =
-        TextureAtlasLayout::from_grid(Vec2::new(SPRITE_WIDTH, SPRITE_HEIGHT), 8, 5, None, None);
+        TextureAtlasLayout::from_grid(Vec2::new(SPRITE_WIDTH, SPRITE_HEIGHT), 8, 7, None, None);
     let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    let animation_indices = AnimationIndices { first: 0, last: 7 };
     commands
         .spawn((
-            SpriteSheetBundle {
-                texture,
-                atlas: TextureAtlas {
-                    layout: texture_atlas_layout,
-                    index: animation_indices.first,
-                },
-                transform: Transform::from_xyz(0.0, -20.0, 0.0).with_scale(Vec3::splat(SPRITE_SCALE)),
-                ..default()
-            },
-            animation_indices,
-            AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+            SpatialBundle::from_transform(
+                Transform::from_xyz(0.0, -20.0, 0.0).with_scale(Vec3::splat(SPRITE_SCALE)),
+            ),
             Player {
                 state: PlayerState::Idle,
                 previous_state: PlayerState::Idle,
                 facing_direction: Vec2::X,
                 jump_count: 0,
                 can_jump: true,
+                launched: false,
                 //     // jump_timer: Timer::from_seconds(0.4, TimerMode::Repeating),
             },
             Controller {
@@ -128,11 +226,12 @@ fn setup(
                 action_vector: 0,
             },
             InhibitionTimer(Timer::from_seconds(0.25, TimerMode::Once)),
+            DropThrough::default(),
             CoyoteTimer(Timer::from_seconds(0.1, TimerMode::Once)),
-            JumpParticulesTimer(Timer::from_seconds(0.1, TimerMode::Once)),
             Grounded(false),
             OnWall(false),
             EdgeGrab(false),
+            Support::default(),
             RigidBody::Dynamic,
         ))
         .insert(Collider::capsule(
@@ -145,5 +244,76 @@ fn setup(
         .insert(LockedAxes::ROTATION_LOCKED)
         .insert(Velocity::default())
         .insert(GravityScale(16.0))
-        .insert(ExternalForce::default());
+        .insert(ExternalForce::default())
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteSheetBundle {
+                    texture,
+                    atlas: TextureAtlas {
+                        layout: texture_atlas_layout,
+                        index: 0,
+                    },
+                    ..default()
+                },
+                SpriteAnimation::new(asset_server.load(PLAYER_ANIMATIONS)),
+                SquashStretch::default(),
+                PlayerSprite,
+            ));
+        });
+}
+
+/// Put the player back at the spawn point in a clean state, after dying or
+/// when restarting the level
+#[allow(clippy::too_many_arguments)]
+fn respawn_player(
+    mut died_event: EventReader<PlayerDied>,
+    mut restart_event: EventReader<RestartEvent>,
+    spawn_point: Res<SpawnPoint>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut query: Query<(&mut Player, &mut Controller, &mut Transform, &mut Velocity, &mut ExternalForce, &mut GravityScale)>,
+    mut timer_query: Query<(&mut InhibitionTimer, &mut CoyoteTimer, &mut DropThrough, &mut Support), With<Player>>,
+    mut buffured_jump: ResMut<BufferedJump>,
+    mut show_holds_event: EventWriter<ShowHolds>,
+    mut camera_query: Query<&mut CameraRig>,
+) {
+    let died = !died_event.is_empty();
+    let restart = !restart_event.is_empty();
+    died_event.clear();
+    restart_event.clear();
+
+    if !died && !restart {
+        return;
+    }
+
+    // Reloading the level moves the player to the level start
+    if restart {
+        current_level.set_changed();
+    }
+
+    let (mut player, mut controller, mut transform, mut velocity, mut force, mut gravity_scale) = query.single_mut();
+    player.reset();
+    controller.action = Action::None;
+    controller.action_vector = 0;
+    transform.translation.x = spawn_point.0.x;
+    transform.translation.y = spawn_point.0.y;
+    *velocity = Velocity::zero();
+    force.force = Vec2::ZERO;
+    gravity_scale.0 = 16.0;
+
+    let (mut inhibition_timer, mut coyote_timer, mut drop_through, mut support) = timer_query.single_mut();
+    *support = Support::default();
+    *drop_through = DropThrough::default();
+    let duration = inhibition_timer.duration();
+    inhibition_timer.set_elapsed(duration);
+    let duration = coyote_timer.duration();
+    coyote_timer.set_elapsed(duration);
+    buffured_jump.should_jump = false;
+    let duration = buffured_jump.timer.duration();
+    buffured_jump.timer.set_elapsed(duration);
+
+    show_holds_event.send(ShowHolds(HoldsVisibility::Hidden));
+
+    for mut rig in camera_query.iter_mut() {
+        rig.snap();
+    }
 }
@@ -20,15 +20,19 @@ use bevy_rapier2d::prelude::*;
 
 //  use crate::physics::{CollideEvent, CollideWith};
 use crate::player::*;
-use crate::world::{TILE_SCALED, holds::ShowHolds, holds::HoldsVisibility};
+use crate::world::{TILE_SCALED, holds::ShowHolds, holds::HoldsVisibility, holds::WallHold};
+use crate::world::one_way::{DropThrough, OneWayPlatform};
+use crate::world::springs::Launched;
 
-const MAX_RUNNING_SPEED: f32 = 250.0;
+pub const MAX_RUNNING_SPEED: f32 = 250.0;
 // Force to apply to reach MAX_RUNNING_SPEED in 2 secs
 const RUNNING_FORCE: f32 = PLAYER_MASS / 2.0 * 20.0 * MAX_RUNNING_SPEED;
 
-const JUMP_SPEED: f32 = 600.0;
-const MAX_FALLING_SPEED: f32 = 600.0;
+pub const JUMP_SPEED: f32 = 600.0;
+pub const MAX_FALLING_SPEED: f32 = 600.0;
 const MAX_WALL_SLIDING_SPEED: f32 = 100.0;
+// Minimal falling speed on a wall to be sliding down rather than holding it
+pub const WALL_SLIDE_SPEED: f32 = 20.0;
 
 // Define if the player can jump more than once before been grounded or on wall again
 pub const PLAYER_MAX_JUMP_COUNT: u32 = 1;
@@ -40,8 +44,23 @@ pub struct InhibitionTimer(pub Timer);
 pub struct CoyoteTimer(pub Timer);
 
 /// Inform other system that the player just performed a jump
+#[derive(Event)]
+pub struct JustJumped {
+    /// Jumped away from a wall rather than from the ground
+    pub from_wall: bool,
+}
+
+/// Inform other system that the player grabbed the wall and climbed, the
+/// grab fails when the wall has no holds
+#[derive(Event)]
+pub struct HoldGrab {
+    pub success: bool,
+    pub action: Action,
+}
+
+/// Inform other system that the player lost the grip on the wall holds
 #[derive(Event, Default)]
-pub struct JustJumped;
+pub struct LostGrip;
 
 /// Inform the coyote system that a late jump can be possible
 #[derive(Event, Default)]
@@ -68,18 +87,23 @@ pub fn player_movement(
     mut query: Query<(&Controller, &mut Player)>,
     sense_query: Query<(&Grounded, &OnWall, &EdgeGrab), With<Player>>,
     mut modifier_query: Query<(&mut ExternalForce, &mut Velocity, &mut Transform, &mut GravityScale), With<Player>>,
-    mut timer_query: Query<&mut InhibitionTimer, With<Player>>,
+    mut timer_query: Query<(&mut InhibitionTimer, &mut DropThrough, &Support), With<Player>>,
+    one_way_query: Query<(), With<OneWayPlatform>>,
+    hold_query: Query<(), With<WallHold>>,
     mut action_event: EventReader<ActionEvent>,
     mut jump_event: EventWriter<JustJumped>,
     mut coyote_event: EventWriter<CoyoteStart>,
     mut buffured_jump: ResMut<BufferedJump>,
     mut show_holds_event: EventWriter<ShowHolds>,
+    mut lost_grip_event: EventWriter<LostGrip>,
+    mut grab_event: EventWriter<HoldGrab>,
     time: Res<Time>,
 ) {
     let (controller, mut player) = query.single_mut();
     let (grounded, on_wall, edge_grab) = sense_query.single();
     let (mut force, mut velocity, mut transform, mut gravity_scale) = modifier_query.single_mut();
-    let mut inhibition_timer = timer_query.single_mut();
+    let (mut inhibition_timer, mut drop_through, support) = timer_query.single_mut();
+    drop_through.tick(time.delta());
 
     let player_still: bool = velocity.linvel.x < 20.0 && velocity.linvel.x > -20.0 && controller.direction.x == 0.0;
 
@@ -103,6 +127,11 @@ pub fn player_movement(
         }
     }
 
+    // The launch is over at the top of the trajectory
+    if player.launched && velocity.linvel.y <= 0.0 {
+        player.launched = false;
+    }
+
     // Define if player can jump
     if (grounded.0 || on_wall.0) && controller.jump_released {
         player.jump_count = 0;
@@ -133,6 +162,15 @@ pub fn player_movement(
                 player.state = PlayerState::OnWall;
                 show_holds_event.send(ShowHolds(HoldsVisibility::Hidden));
             },
+            Action::DropDown => {
+                let on_one_way = support.ground.is_some_and(|entity| one_way_query.contains(entity));
+                if grounded.0 && on_one_way {
+                    debug!("Drop down");
+                    drop_through.reset();
+                } else {
+                    buffured_jump.timer.reset();
+                }
+            },
             Action::GrabLeft | Action::GrabRight | Action::GrabUp | Action::GrabDown => {
                 info!("Grab hold");
                 grabe_input = Some(event.0);
@@ -164,6 +202,10 @@ pub fn player_movement(
         } else {
             coyote_event.send_default();
         }
+
+        if player.previous_state == PlayerState::Climbing {
+            lost_grip_event.send_default();
+        }
     }
 
     // info!("Player state {:?}", player.state);
@@ -188,10 +230,11 @@ pub fn player_movement(
         }
         PlayerState::Walking => {}
         PlayerState::Running => {
+            let surface = support.ground_surface;
             if controller.direction.x != 0.0 {
-                apply_horizontal_force(&controller, &mut force, &mut velocity);
+                apply_horizontal_force(controller, &mut force, &mut velocity, surface.acceleration());
             } else if controller.action == Action::None {
-                stop_horizontal_velocity(&mut velocity, &mut force, RUNNING_FORCE * 2.0);
+                stop_horizontal_velocity(&mut velocity, &mut force, RUNNING_FORCE * 2.0 * surface.braking());
             }
 
             if buffured_jump.should_jump {
@@ -203,12 +246,13 @@ pub fn player_movement(
 
             // Keep X movement control
             if controller.direction.x != 0.0 {
-                apply_horizontal_force(&controller, &mut force, &mut velocity);
+                apply_horizontal_force(controller, &mut force, &mut velocity, 1.0);
             } else if controller.action == Action::None {
                 stop_horizontal_velocity(&mut velocity, &mut force, RUNNING_FORCE);
             }
 
-            if controller.action != Action::Jump {
+            // Releasing jump shortens a jump, but not a launch
+            if controller.action != Action::Jump && !player.launched {
                 stop_vertical_velocity(&mut velocity, &mut force, JUMP_SPEED);
             }
 
@@ -228,8 +272,9 @@ pub fn player_movement(
         },
         PlayerState::OnWall => {
             gravity_scale.0 = 16.0;
-            if velocity.linvel.y < -MAX_WALL_SLIDING_SPEED {
-                velocity.linvel.y = -MAX_WALL_SLIDING_SPEED;
+            let max_sliding_speed = MAX_WALL_SLIDING_SPEED * support.wall_surface.wall_sliding();
+            if velocity.linvel.y < -max_sliding_speed {
+                velocity.linvel.y = -max_sliding_speed;
             }
 
             if buffured_jump.should_jump {
@@ -241,8 +286,10 @@ pub fn player_movement(
         PlayerState::Climbing => {
             gravity_scale.0 = 0.0;
             velocity.linvel = Vec2::ZERO;
-            if grabe_input.is_some() {
+            if let Some(action) = grabe_input {
                 transform.translation.y += TILE_SCALED/2.0;
+                let success = support.wall.is_some_and(|entity| hold_query.contains(entity));
+                grab_event.send(HoldGrab { success, action });
             }
         }
     }
@@ -250,6 +297,30 @@ pub fn player_movement(
     player.previous_state = player.state;
 }
 
+/// The player slides down the wall it touches, for the effects of the slide
+pub fn is_wall_sliding(player: &Player, velocity: &Velocity) -> bool {
+    player.state == PlayerState::OnWall && velocity.linvel.y < -WALL_SLIDE_SPEED
+}
+
+/// Throw the player from a spring or a bounce pad, the jump is available
+/// again while in the air
+pub fn launch_player(
+    mut launched_event: EventReader<Launched>,
+    mut query: Query<(&mut Player, &mut Velocity, &mut InhibitionTimer)>,
+) {
+    let (mut player, mut velocity, mut inhibition_timer) = query.single_mut();
+
+    for event in launched_event.read() {
+        player.launched = true;
+        player.jump_count = 0;
+        player.can_jump = true;
+        velocity.linvel = event.velocity;
+        // Keep the player input from cancelling the launch right away
+        inhibition_timer.set_duration(event.inhibition);
+        inhibition_timer.reset();
+    }
+}
+
 pub fn coyote_jump(
     mut query: Query<(&Controller, &mut Player)>,
     mut modifier_query: Query<&mut Velocity, With<Player>>,
@@ -287,8 +358,9 @@ fn apply_horizontal_force(
     controller: &Controller,
     force: &mut ExternalForce,
     velocity: &mut Velocity,
+    acceleration: f32,
 ) {
-    force.force.x = controller.direction.x * RUNNING_FORCE;
+    force.force.x = controller.direction.x * RUNNING_FORCE * acceleration;
 
     if velocity.linvel.x > MAX_RUNNING_SPEED {
         velocity.linvel.x = MAX_RUNNING_SPEED;
@@ -311,7 +383,7 @@ fn stop_horizontal_velocity(velocity: &mut Velocity, force: &mut ExternalForce,
 fn jump(player: &mut Player, controller: &Controller, velocity: &mut Velocity, event: &mut EventWriter<JustJumped>) {
     if player.can_jump {
         debug!("Jump");
-        event.send_default();
+        event.send(JustJumped { from_wall: false });
         player.jump_count += 1;
         velocity.linvel.y = JUMP_SPEED;
     }
@@ -320,7 +392,7 @@ fn jump(player: &mut Player, controller: &Controller, velocity: &mut Velocity, e
 fn wall_jump(player: &mut Player, velocity: &mut Velocity, event: &mut EventWriter<JustJumped>) {
     if player.can_jump {
         debug!("Wall jump");
-        event.send_default();
+        event.send(JustJumped { from_wall: true });
         player.jump_count += 1;
         velocity.linvel.y = JUMP_SPEED;
         velocity.linvel.x = -player.facing_direction.x * MAX_RUNNING_SPEED;
@@ -0,0 +1,293 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Particle effects of the player, played by a pool of emitters spawned once.
+/// Each preset has its own emitters, so an effect can start while the
+/// previous one is still emitting. The emitters are placed at the player feet
+/// or on the side of the wall when triggered, the particles already emitted
+/// stay where they are.
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_particle_systems::*;
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::player::*;
+use crate::player::movement::is_wall_sliding;
+use crate::world::themes::LevelTheme;
+
+const FEET_OFFSET: f32 = SPRITE_HEIGHT / 2.0 * SPRITE_SCALE;
+// Horizontal distance between the player center and a wall it touches
+const WALL_OFFSET: f32 = 2.0 * SPRITE_SCALE;
+// Minimal speed to raise dust when running
+const RUN_DUST_SPEED: f32 = 100.0;
+
+const DUST_COLOR: Color = Color::rgb(0.8, 0.75, 0.65);
+const SPARK_COLOR: Color = Color::rgb(1.0, 0.8, 0.3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticlePreset {
+    Jump,
+    Land,
+    /// Continuous while running fast enough
+    RunDust,
+    /// Continuous while sliding down a wall
+    WallSlide,
+    WallJump,
+}
+
+impl ParticlePreset {
+    const ALL: [ParticlePreset; 5] = [
+        ParticlePreset::Jump,
+        ParticlePreset::Land,
+        ParticlePreset::RunDust,
+        ParticlePreset::WallSlide,
+        ParticlePreset::WallJump,
+    ];
+
+    /// Continuous effects are triggered again at every update and only need
+    /// one emitter
+    fn pool_size(&self) -> usize {
+        match self {
+            ParticlePreset::RunDust | ParticlePreset::WallSlide => 1,
+            _ => 2,
+        }
+    }
+
+    /// Time spent emitting particles after the last trigger
+    fn emission_duration(&self) -> f32 {
+        match self {
+            ParticlePreset::Jump => 0.1,
+            ParticlePreset::Land | ParticlePreset::WallJump => 0.08,
+            ParticlePreset::RunDust | ParticlePreset::WallSlide => 0.1,
+        }
+    }
+
+    /// Position of the emitter relative to the player, `side` is the
+    /// horizontal direction the player faces
+    fn offset(&self, side: f32) -> Vec3 {
+        match self {
+            ParticlePreset::Jump | ParticlePreset::Land | ParticlePreset::RunDust => {
+                Vec3::new(0.0, -FEET_OFFSET, 0.0)
+            }
+            ParticlePreset::WallSlide | ParticlePreset::WallJump => Vec3::new(side * WALL_OFFSET, 0.0, 0.0),
+        }
+    }
+
+    fn emitter_shape(&self, side: f32) -> EmitterShape {
+        // Direction pointing away from the wall, or behind the player
+        let back = if side > 0.0 { PI } else { 0.0 };
+        let (length, angle) = match self {
+            ParticlePreset::Jump => (
+                SPRITE_WIDTH / 2.0 * SPRITE_SCALE,
+                JitteredValue::jittered(PI / 2.0, -1.5..1.5),
+            ),
+            ParticlePreset::Land => (
+                SPRITE_WIDTH * SPRITE_SCALE,
+                JitteredValue::jittered(PI / 2.0, -1.2..1.2),
+            ),
+            ParticlePreset::RunDust => (
+                SPRITE_WIDTH / 4.0 * SPRITE_SCALE,
+                // Halfway between behind and up
+                JitteredValue::jittered((back + PI / 2.0) / 2.0, -0.4..0.4),
+            ),
+            ParticlePreset::WallSlide => (
+                SPRITE_SCALE,
+                JitteredValue::jittered(back, -0.8..0.8),
+            ),
+            ParticlePreset::WallJump => (
+                SPRITE_HEIGHT / 2.0 * SPRITE_SCALE,
+                JitteredValue::jittered(back, -0.6..0.6),
+            ),
+        };
+        EmitterShape::Line(Line { length, angle })
+    }
+
+    /// Colours following the level theme, none for the effects that keep theirs
+    fn themed_color(&self, level_theme: &LevelTheme) -> Option<ColorOverTime> {
+        match self {
+            ParticlePreset::Land | ParticlePreset::RunDust => {
+                let dust = level_theme.0.dust_color;
+                Some(ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(dust, 0.0),
+                    CurvePoint::new(dust.with_a(0.0), 1.0),
+                ])))
+            }
+            _ => None,
+        }
+    }
+
+    fn particle_system(&self) -> ParticleSystem {
+        let particle_system = ParticleSystem {
+            emitter_shape: self.emitter_shape(1.0),
+            looping: true,
+            system_duration_seconds: 0.2,
+            ..ParticleSystem::default()
+        };
+
+        match self {
+            ParticlePreset::Jump => ParticleSystem {
+                max_particles: 10,
+                spawn_rate_per_second: 50.0.into(),
+                initial_speed: JitteredValue::jittered(10.0, 5.0..20.0),
+                lifetime: JitteredValue::jittered(0.2, -0.1..0.2),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(Color::WHITE, 0.0),
+                    CurvePoint::new(Color::rgba(0.5, 0.5, 1.0, 0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(3.0, -1.0..2.0),
+                ..particle_system
+            },
+            ParticlePreset::Land => ParticleSystem {
+                max_particles: 16,
+                spawn_rate_per_second: 150.0.into(),
+                initial_speed: JitteredValue::jittered(30.0, -10.0..20.0),
+                lifetime: JitteredValue::jittered(0.3, -0.1..0.1),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(DUST_COLOR, 0.0),
+                    CurvePoint::new(Color::rgba(0.8, 0.75, 0.65, 0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(3.0, -1.0..1.0),
+                ..particle_system
+            },
+            ParticlePreset::RunDust => ParticleSystem {
+                max_particles: 8,
+                spawn_rate_per_second: 20.0.into(),
+                initial_speed: JitteredValue::jittered(15.0, -5.0..5.0),
+                lifetime: JitteredValue::jittered(0.3, -0.1..0.1),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(DUST_COLOR, 0.0),
+                    CurvePoint::new(Color::rgba(0.8, 0.75, 0.65, 0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(2.0, -1.0..1.0),
+                ..particle_system
+            },
+            ParticlePreset::WallSlide => ParticleSystem {
+                max_particles: 12,
+                spawn_rate_per_second: 40.0.into(),
+                initial_speed: JitteredValue::jittered(25.0, -10.0..15.0),
+                lifetime: JitteredValue::jittered(0.2, -0.1..0.1),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(SPARK_COLOR, 0.0),
+                    CurvePoint::new(Color::rgba(1.0, 0.3, 0.1, 0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(1.5, -0.5..0.5),
+                ..particle_system
+            },
+            ParticlePreset::WallJump => ParticleSystem {
+                max_particles: 16,
+                spawn_rate_per_second: 200.0.into(),
+                initial_speed: JitteredValue::jittered(60.0, -20.0..20.0),
+                lifetime: JitteredValue::jittered(0.25, -0.1..0.1),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(Color::WHITE, 0.0),
+                    CurvePoint::new(Color::rgba(0.5, 0.5, 1.0, 0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(3.0, -1.0..1.0),
+                ..particle_system
+            },
+        }
+    }
+}
+
+/// Ask for a particle effect at the player position
+#[derive(Event)]
+pub struct EmitParticles {
+    pub preset: ParticlePreset,
+    /// Horizontal direction of the wall, or of the movement
+    pub side: f32,
+}
+
+/// Emitter of the pool, playing while its timer runs
+#[derive(Component)]
+pub struct ParticleEmitter {
+    preset: ParticlePreset,
+    timer: Timer,
+}
+
+pub fn setup(mut commands: Commands) {
+    for preset in ParticlePreset::ALL {
+        for _ in 0..preset.pool_size() {
+            let mut timer = Timer::from_seconds(preset.emission_duration(), TimerMode::Once);
+            let duration = timer.duration();
+            timer.set_elapsed(duration);
+
+            commands
+                .spawn(ParticleSystemBundle {
+                    particle_system: preset.particle_system(),
+                    ..ParticleSystemBundle::default()
+                })
+                .insert(ParticleEmitter { preset, timer });
+        }
+    }
+}
+
+/// Start the effects from the player state transitions
+pub fn trigger_particles(
+    mut jump_event: EventReader<JustJumped>,
+    mut landed_event: EventReader<Landed>,
+    query: Query<(&Player, &Velocity)>,
+    mut emit_event: EventWriter<EmitParticles>,
+) {
+    let (player, velocity) = query.single();
+    let side = player.facing_direction.x;
+
+    for event in jump_event.read() {
+        let preset = if event.from_wall { ParticlePreset::WallJump } else { ParticlePreset::Jump };
+        emit_event.send(EmitParticles { preset, side });
+    }
+
+    if !landed_event.is_empty() {
+        landed_event.clear();
+        emit_event.send(EmitParticles { preset: ParticlePreset::Land, side });
+    }
+
+    if player.state == PlayerState::Running && velocity.linvel.x.abs() > RUN_DUST_SPEED {
+        emit_event.send(EmitParticles {
+            preset: ParticlePreset::RunDust,
+            side: velocity.linvel.x.signum(),
+        });
+    }
+
+    if is_wall_sliding(player, velocity) {
+        emit_event.send(EmitParticles { preset: ParticlePreset::WallSlide, side });
+    }
+}
+
+pub fn play_emitters(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut emit_event: EventReader<EmitParticles>,
+    level_theme: Res<LevelTheme>,
+    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<(Entity, &mut ParticleEmitter, &mut ParticleSystem, &mut Transform), Without<Player>>,
+) {
+    let player_transform = player_query.single();
+
+    for event in emit_event.read() {
+        // An idle emitter of the preset, or the one emitting for the longest time
+        let emitter = query
+            .iter_mut()
+            .filter(|(_, emitter, _, _)| emitter.preset == event.preset)
+            .max_by(|(_, a, _, _), (_, b, _, _)| a.timer.fraction().total_cmp(&b.timer.fraction()));
+        let Some((entity, mut emitter, mut particle_system, mut transform)) = emitter else {
+            continue;
+        };
+
+        transform.translation = player_transform.translation + event.preset.offset(event.side);
+        particle_system.emitter_shape = event.preset.emitter_shape(event.side);
+        if let Some(color) = event.preset.themed_color(&level_theme) {
+            particle_system.color = color;
+        }
+        emitter.timer.reset();
+        commands.entity(entity).insert(Playing);
+    }
+
+    for (entity, mut emitter, _, _) in query.iter_mut() {
+        emitter.timer.tick(time.delta());
+        if emitter.timer.just_finished() {
+            commands.entity(entity).remove::<Playing>();
+        }
+    }
+}
@@ -4,9 +4,16 @@
 
 /// Various sensor and detection for world element
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
 
 use crate::DebugGizmos;
 use crate::player::*;
+use crate::world::one_way::{DropThrough, OneWayPlatform};
+use crate::world::surfaces::Surface;
+
+// Minimal distance between the player center and a one-way platform to
+// stand on it, closer means the player is passing through from below
+const ONE_WAY_MIN_TOI: f32 = 6.0 * SPRITE_SCALE;
 
 pub fn facing_direction(mut query: Query<(&Controller, &mut Player)>) {
     let (controller, mut player) = query.single_mut();
@@ -17,25 +24,45 @@ pub fn facing_direction(mut query: Query<(&Controller, &mut Player)>) {
     }
 }
 
+/// Inform other system that the player just touched the ground, with the
+/// vertical speed at the moment of the impact
+#[derive(Event)]
+pub struct Landed {
+    pub impact_speed: f32,
+}
+
+#[allow(clippy::type_complexity)]
 pub fn ground_detection(
-    mut query: Query<(&Transform, Entity, &mut Grounded), With<Player>>,
+    mut query: Query<(&Transform, Entity, &mut Grounded, &mut Support, &Velocity, &DropThrough), With<Player>>,
+    one_way_query: Query<(), With<OneWayPlatform>>,
+    surface_query: Query<&Surface>,
     rapier_ctx: Res<RapierContext>,
     debug: Res<DebugGizmos>,
     mut gizmos: Gizmos,
+    mut landed_event: EventWriter<Landed>,
 ) {
-    let (transform, entity, mut grounded) = query.single_mut();
+    let (transform, entity, mut grounded, mut support, velocity, drop_through) = query.single_mut();
+    let was_grounded = grounded.0;
+
+    // One-way platforms only count when landing on them from above
+    let is_ground = |(hit_entity, toi): &(Entity, f32)| {
+        !one_way_query.contains(*hit_entity)
+            || (drop_through.finished() && velocity.linvel.y <= 0.0 && *toi >= ONE_WAY_MIN_TOI)
+    };
 
     // Ray casting for ground detection
     let ray_pos = transform.translation.truncate() - Vec2::new(4.0, 0.0);
     let ray_dir = Vec2::NEG_Y;
     let max_toi = 2.1 * 16.0;
     let solid = true;
-    let filter = QueryFilter::default().exclude_rigid_body(entity);
+    let filter = QueryFilter::default().exclude_rigid_body(entity).exclude_sensors();
 
-    if let Some((_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter) {
+    if let Some((hit_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter).filter(is_ground) {
         grounded.0 = true;
+        support.ground = Some(hit_entity);
     } else {
         grounded.0 = false;
+        support.ground = None;
     }
 
     if debug.0 {
@@ -46,9 +73,10 @@ pub fn ground_detection(
     let ray_pos = transform.translation.truncate() + Vec2::new(4.0, 0.0);
 
     if !grounded.0 {
-        if let Some((_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter)
+        if let Some((hit_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter).filter(is_ground)
         {
             grounded.0 = true;
+            support.ground = Some(hit_entity);
         } else {
             grounded.0 = false;
         }
@@ -57,28 +85,52 @@ pub fn ground_detection(
     if debug.0 {
         gizmos.ray_2d(ray_pos, ray_dir * max_toi, Color::GREEN);
     }
+
+    support.ground_surface = support
+        .ground
+        .and_then(|entity| surface_query.get(entity).ok())
+        .copied()
+        .unwrap_or_default();
+
+    if !was_grounded && grounded.0 {
+        landed_event.send(Landed { impact_speed: -velocity.linvel.y });
+    }
 }
 
 pub fn wall_detection(
-    mut query: Query<(&Transform, Entity, &mut OnWall, &Player)>,
+    mut query: Query<(&Transform, Entity, &mut OnWall, &mut Support, &Player)>,
+    one_way_query: Query<(), With<OneWayPlatform>>,
+    surface_query: Query<&Surface>,
     rapier_ctx: Res<RapierContext>,
     debug: Res<DebugGizmos>,
     mut gizmos: Gizmos,
 ) {
-    let (transform, entity, mut on_wall, player) = query.single_mut();
+    let (transform, entity, mut on_wall, mut support, player) = query.single_mut();
 
     // Ray casting for wall detection
     let ray_pos = transform.translation.truncate() + Vec2::new(0.0, 0.0);
     let ray_dir = player.facing_direction;
     let max_toi = 1.2 * 16.0;
     let solid = true;
-    let filter = QueryFilter::default().exclude_rigid_body(entity);
-
-    if let Some((_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter) {
+    // One-way platforms can't be climbed
+    let not_one_way = |hit_entity: Entity| !one_way_query.contains(hit_entity);
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors()
+        .predicate(&not_one_way);
+
+    if let Some((hit_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter) {
         on_wall.0 = true;
+        support.wall = Some(hit_entity);
     } else {
         on_wall.0 = false;
+        support.wall = None;
     }
+    support.wall_surface = support
+        .wall
+        .and_then(|entity| surface_query.get(entity).ok())
+        .copied()
+        .unwrap_or_default();
     if debug.0 {
         gizmos.ray_2d(ray_pos, ray_dir * max_toi, Color::GREEN);
     }
@@ -86,6 +138,7 @@ pub fn wall_detection(
 
 pub fn edge_grab_detection(
     mut query: Query<(&Transform, Entity, &mut EdgeGrab, &Player)>,
+    one_way_query: Query<(), With<OneWayPlatform>>,
     rapier_ctx: Res<RapierContext>,
     debug: Res<DebugGizmos>,
     mut gizmos: Gizmos,
@@ -97,7 +150,12 @@ pub fn edge_grab_detection(
     let ray_dir = player.facing_direction;
     let max_toi = 1.2 * 16.0;
     let solid = true;
-    let filter = QueryFilter::default().exclude_rigid_body(entity);
+    // One-way platforms can't be climbed
+    let not_one_way = |hit_entity: Entity| !one_way_query.contains(hit_entity);
+    let filter = QueryFilter::default()
+        .exclude_rigid_body(entity)
+        .exclude_sensors()
+        .predicate(&not_one_way);
 
     if let Some((_entity, _toi)) = rapier_ctx.cast_ray(ray_pos, ray_dir, max_toi, solid, filter) {
         edge_grab.0 = true;
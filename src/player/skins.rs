@@ -0,0 +1,176 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Skins of the player, unlocked by the number of items collected over all
+/// the levels. A skin is either a whole sprite-sheet with the layout of
+/// `PLAYER_SHEET`, or `PLAYER_SHEET` with some colours replaced. The palette
+/// skins are built once the sheet is loaded, on the CPU so they work on every
+/// renderer.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::player::*;
+use crate::save::SaveData;
+
+pub const PLAYER_SHEET: &str = "tileset.png";
+
+/// Colours of `PLAYER_SHEET`
+const OUTFIT: [u8; 3] = [142, 171, 201];
+const OUTFIT_SHADE: [u8; 3] = [77, 106, 168];
+const HAIR: [u8; 3] = [171, 69, 58];
+const HAIR_LIGHT: [u8; 3] = [196, 93, 58];
+const BAG: [u8; 3] = [127, 162, 91];
+const BAG_SHADE: [u8; 3] = [62, 111, 67];
+
+const EMBER: &[([u8; 3], [u8; 3])] = &[
+    (OUTFIT, [214, 120, 70]),
+    (OUTFIT_SHADE, [160, 70, 50]),
+    (BAG, [90, 80, 80]),
+    (BAG_SHADE, [60, 52, 52]),
+];
+
+const MOSS: &[([u8; 3], [u8; 3])] = &[
+    (OUTFIT, [120, 170, 100]),
+    (OUTFIT_SHADE, [70, 120, 70]),
+    (HAIR, [110, 80, 50]),
+    (HAIR_LIGHT, [140, 105, 65]),
+];
+
+pub enum SkinTexture {
+    /// Path of a sprite-sheet with the same layout as `PLAYER_SHEET`
+    Sheet(&'static str),
+    /// Colours of `PLAYER_SHEET` to replace, alpha is kept
+    Palette(&'static [([u8; 3], [u8; 3])]),
+}
+
+pub struct Skin {
+    /// Identifier stored in the save
+    pub id: &'static str,
+    pub name: &'static str,
+    pub texture: SkinTexture,
+    /// Number of collected items needed to use the skin
+    pub unlock: usize,
+}
+
+pub const SKINS: &[Skin] = &[
+    Skin {
+        id: "default",
+        name: "Classic",
+        texture: SkinTexture::Sheet(PLAYER_SHEET),
+        unlock: 0,
+    },
+    Skin {
+        id: "ember",
+        name: "Ember",
+        texture: SkinTexture::Palette(EMBER),
+        unlock: 3,
+    },
+    Skin {
+        id: "moss",
+        name: "Moss",
+        texture: SkinTexture::Palette(MOSS),
+        unlock: 6,
+    },
+    Skin {
+        id: "night",
+        name: "Night",
+        texture: SkinTexture::Sheet("skins/night.png"),
+        unlock: 10,
+    },
+];
+
+/// Skin with the given identifier, the default one when unknown
+pub fn skin(id: &str) -> &'static Skin {
+    SKINS.iter().find(|skin| skin.id == id).unwrap_or(&SKINS[0])
+}
+
+/// Number of items collected in all the levels
+pub fn collected_count(save: &SaveData) -> usize {
+    save.collectibles.values().map(Vec::len).sum()
+}
+
+/// Skin selected in the settings, if it is still unlocked
+pub fn selected_skin(save: &SaveData) -> &'static Skin {
+    let skin = skin(&save.settings.skin);
+    if skin.unlock <= collected_count(save) {
+        skin
+    } else {
+        &SKINS[0]
+    }
+}
+
+/// Unlocked skin following `id`, going back to the first one after the last
+pub fn next_unlocked_skin(id: &str, collected: usize) -> &'static Skin {
+    let current = SKINS.iter().position(|skin| skin.id == id).unwrap_or(0);
+    (1..=SKINS.len())
+        .map(|offset| &SKINS[(current + offset) % SKINS.len()])
+        .find(|skin| skin.unlock <= collected)
+        .unwrap_or(&SKINS[0])
+}
+
+/// Texture of each skin, by identifier
+#[derive(Resource)]
+pub struct SkinTextures {
+    base: Handle<Image>,
+    textures: HashMap<&'static str, Handle<Image>>,
+}
+
+pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mut textures = HashMap::new();
+    for skin in SKINS {
+        if let SkinTexture::Sheet(path) = skin.texture {
+            textures.insert(skin.id, asset_server.load(path));
+        }
+    }
+
+    commands.insert_resource(SkinTextures {
+        base: asset_server.load(PLAYER_SHEET),
+        textures,
+    });
+}
+
+/// Build the palette skins once `PLAYER_SHEET` is loaded
+pub fn build_palettes(mut skin_textures: ResMut<SkinTextures>, mut images: ResMut<Assets<Image>>) {
+    if skin_textures.textures.len() == SKINS.len() {
+        return;
+    }
+    let Some(base) = images.get(&skin_textures.base).cloned() else {
+        return;
+    };
+
+    for skin in SKINS {
+        let SkinTexture::Palette(palette) = skin.texture else {
+            continue;
+        };
+
+        let mut image = base.clone();
+        // The sheet is loaded as 8 bits RGBA
+        for pixel in image.data.chunks_exact_mut(4) {
+            if let Some((_, to)) = palette.iter().find(|(from, _)| pixel[..3] == from[..]) {
+                pixel[..3].copy_from_slice(to);
+            }
+        }
+        skin_textures.textures.insert(skin.id, images.add(image));
+    }
+}
+
+/// Give the player sprite the texture of the selected skin
+pub fn apply_skin(
+    save: Res<SaveData>,
+    skin_textures: Res<SkinTextures>,
+    mut query: Query<&mut Handle<Image>, With<PlayerSprite>>,
+) {
+    let skin = selected_skin(&save);
+    let Some(texture) = skin_textures.textures.get(skin.id) else {
+        // Palette not built yet
+        return;
+    };
+
+    for mut handle in query.iter_mut() {
+        if *handle != *texture {
+            *handle = texture.clone();
+        }
+    }
+}
@@ -0,0 +1,93 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Squash and stretch of the player sprite, stretching on jumps, squashing
+/// on landings and leaning while running.
+/// Only the sprite child is deformed, the physics entity and its collider
+/// keep their transform.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::*;
+
+// Deformation applied when jumping, x is reduced as much as y is increased
+const JUMP_STRETCH: f32 = 0.3;
+// Deformation applied for the hardest landings
+const MAX_LANDING_SQUASH: f32 = 0.4;
+// Landing speed giving the maximal squash, the maximal falling speed
+const HARD_LANDING_SPEED: f32 = 600.0;
+// Spring bringing the sprite back to its size
+const STIFFNESS: f32 = 300.0;
+const DAMPING: f32 = 14.0;
+// Rotation in radians at full running speed
+const MAX_LEAN: f32 = 0.12;
+const LEAN_SPEED: f32 = 10.0;
+// Longest step of the spring, a longer frame would make it diverge
+const MAX_DELTA: f32 = 1.0 / 30.0;
+
+#[derive(Component)]
+pub struct SquashStretch {
+    /// Current deformation, 1.0 on both axes is the sprite size
+    scale: Vec2,
+    /// Speed of the deformation
+    velocity: Vec2,
+    lean: f32,
+}
+
+impl Default for SquashStretch {
+    fn default() -> Self {
+        SquashStretch {
+            scale: Vec2::ONE,
+            velocity: Vec2::ZERO,
+            lean: 0.0,
+        }
+    }
+}
+
+pub fn trigger_squash_stretch(
+    mut jump_event: EventReader<JustJumped>,
+    mut landed_event: EventReader<Landed>,
+    mut query: Query<&mut SquashStretch, With<PlayerSprite>>,
+) {
+    let mut squash_stretch = query.single_mut();
+
+    if !jump_event.is_empty() {
+        jump_event.clear();
+        squash_stretch.scale = Vec2::new(1.0 - JUMP_STRETCH, 1.0 + JUMP_STRETCH);
+    }
+
+    for event in landed_event.read() {
+        let squash = (event.impact_speed / HARD_LANDING_SPEED).clamp(0.0, 1.0) * MAX_LANDING_SQUASH;
+        squash_stretch.scale = Vec2::new(1.0 + squash, 1.0 - squash);
+    }
+}
+
+pub fn animate_squash_stretch(
+    time: Res<Time>,
+    player_query: Query<(&Player, &Velocity)>,
+    mut query: Query<(&mut SquashStretch, &mut Transform), With<PlayerSprite>>,
+) {
+    let (player, velocity) = player_query.single();
+    let (mut squash_stretch, mut transform) = query.single_mut();
+    let delta = time.delta_seconds().min(MAX_DELTA);
+
+    // Damped spring back to the sprite size
+    let acceleration = -STIFFNESS * (squash_stretch.scale - Vec2::ONE) - DAMPING * squash_stretch.velocity;
+    squash_stretch.velocity += acceleration * delta;
+    let velocity_step = squash_stretch.velocity * delta;
+    squash_stretch.scale += velocity_step;
+
+    // Lean toward the running direction
+    let target_lean = if player.state == PlayerState::Running {
+        -(velocity.linvel.x / MAX_RUNNING_SPEED).clamp(-1.0, 1.0) * MAX_LEAN
+    } else {
+        0.0
+    };
+    squash_stretch.lean += (target_lean - squash_stretch.lean) * (LEAN_SPEED * delta).min(1.0);
+
+    transform.scale = squash_stretch.scale.extend(1.0);
+    transform.rotation = Quat::from_rotation_z(squash_stretch.lean);
+    // Keep the feet on the ground, the sprite is deformed from its center
+    transform.translation.y = (squash_stretch.scale.y - 1.0) * SPRITE_HEIGHT / 2.0;
+}
@@ -0,0 +1,171 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// On-screen virtual controls for touch devices (WASM on mobile, Android).
+/// The buttons are drawn with Bevy UI and only set the `VirtualButtons`
+/// resource, the `Controller` is then populated by `keyboard_inputs`.
+use bevy::prelude::*;
+
+use crate::player::controller::*;
+
+const BUTTON_SIZE: f32 = 64.0;
+const BUTTON_MARGIN: f32 = 8.0;
+const SCREEN_MARGIN: f32 = 24.0;
+
+const BUTTON_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+const BUTTON_PRESSED_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+
+/// Root node of the touch controls, hidden until the first touch
+#[derive(Component)]
+pub struct TouchControls;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum TouchButton {
+    Left,
+    Right,
+    Down,
+    Jump,
+    Climb,
+    GrabLeft,
+    GrabRight,
+    GrabUp,
+    GrabDown,
+}
+
+pub fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TouchControls,
+        ))
+        .with_children(|parent| {
+            // D-pad on the bottom left
+            spawn_button(parent, TouchButton::Left, "<", Val::Px(SCREEN_MARGIN), Val::Auto, Val::Px(SCREEN_MARGIN));
+            spawn_button(
+                parent,
+                TouchButton::Right,
+                ">",
+                Val::Px(SCREEN_MARGIN + 2.0 * (BUTTON_SIZE + BUTTON_MARGIN)),
+                Val::Auto,
+                Val::Px(SCREEN_MARGIN),
+            );
+            spawn_button(
+                parent,
+                TouchButton::Down,
+                "v",
+                Val::Px(SCREEN_MARGIN + BUTTON_SIZE + BUTTON_MARGIN),
+                Val::Auto,
+                Val::Px(SCREEN_MARGIN),
+            );
+
+            // Jump and climbing mode above the d-pad
+            spawn_button(
+                parent,
+                TouchButton::Jump,
+                "Jump",
+                Val::Px(SCREEN_MARGIN),
+                Val::Auto,
+                Val::Px(SCREEN_MARGIN + BUTTON_SIZE + BUTTON_MARGIN),
+            );
+            spawn_button(
+                parent,
+                TouchButton::Climb,
+                "Climb",
+                Val::Px(SCREEN_MARGIN + BUTTON_SIZE + BUTTON_MARGIN),
+                Val::Auto,
+                Val::Px(SCREEN_MARGIN + BUTTON_SIZE + BUTTON_MARGIN),
+            );
+
+            // Grab buttons as a diamond on the bottom right
+            let step = BUTTON_SIZE + BUTTON_MARGIN;
+            spawn_button(parent, TouchButton::GrabUp, "W", Val::Auto, Val::Px(SCREEN_MARGIN + step), Val::Px(SCREEN_MARGIN + 2.0 * step));
+            spawn_button(parent, TouchButton::GrabLeft, "A", Val::Auto, Val::Px(SCREEN_MARGIN + 2.0 * step), Val::Px(SCREEN_MARGIN + step));
+            spawn_button(parent, TouchButton::GrabRight, "D", Val::Auto, Val::Px(SCREEN_MARGIN), Val::Px(SCREEN_MARGIN + step));
+            spawn_button(parent, TouchButton::GrabDown, "S", Val::Auto, Val::Px(SCREEN_MARGIN + step), Val::Px(SCREEN_MARGIN));
+        });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, button: TouchButton, label: &str, left: Val, right: Val, bottom: Val) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(BUTTON_SIZE),
+                    height: Val::Px(BUTTON_SIZE),
+                    position_type: PositionType::Absolute,
+                    left,
+                    right,
+                    bottom,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Show the touch controls as soon as the screen is touched
+pub fn show_touch_controls(
+    touches: Res<Touches>,
+    mut query: Query<&mut Visibility, With<TouchControls>>,
+) {
+    if touches.any_just_pressed() {
+        for mut visibility in query.iter_mut() {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+/// Controller implementation for touch screen
+pub fn touch_inputs(
+    touches: Res<Touches>,
+    mut virtual_buttons: ResMut<VirtualButtons>,
+    mut button_query: Query<(&TouchButton, &Node, &GlobalTransform, &mut BackgroundColor)>,
+) {
+    *virtual_buttons = VirtualButtons::default();
+
+    for (button, node, transform, mut color) in button_query.iter_mut() {
+        let rect = node.logical_rect(transform);
+        let pressed = touches.iter().any(|touch| rect.contains(touch.position()));
+
+        if pressed {
+            match button {
+                TouchButton::Left => virtual_buttons.left = true,
+                TouchButton::Right => virtual_buttons.right = true,
+                TouchButton::Down => virtual_buttons.down = true,
+                TouchButton::Jump => virtual_buttons.jump = true,
+                TouchButton::Climb => virtual_buttons.climb = true,
+                TouchButton::GrabLeft => virtual_buttons.grab_left = true,
+                TouchButton::GrabRight => virtual_buttons.grab_right = true,
+                TouchButton::GrabUp => virtual_buttons.grab_up = true,
+                TouchButton::GrabDown => virtual_buttons.grab_down = true,
+            }
+            *color = BUTTON_PRESSED_COLOR.into();
+        } else {
+            *color = BUTTON_COLOR.into();
+        }
+    }
+}
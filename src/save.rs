@@ -0,0 +1,248 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Persistence of the player progress and settings.
+/// The save is stored as RON in the user data directory on native, and in
+/// the browser `localStorage` on the web. It is loaded when building the
+/// app, so it is available before the title screen appears.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioSettings;
+use crate::player::camera_effects::CameraEffectsSettings;
+use crate::player::controller::InputBindings;
+use crate::player::skins::SKINS;
+use crate::world::{CurrentLevel, finish::LevelFinished, levels::LEVELS};
+
+/// Version of the save format, increase it when changing `SaveData`.
+/// Version 1 was a text file only holding the personal bests.
+/// Version 3 added the volume settings.
+/// Version 4 added the player skin.
+const SAVE_VERSION: u32 = 4;
+
+pub struct SavePlugin;
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    /// Number of levels playable from the start of `LEVELS`
+    pub unlocked_levels: usize,
+    /// Best time for each level, indexed by level id
+    pub best_times: HashMap<String, Duration>,
+    /// Grid position of the collected items, indexed by level id
+    pub collectibles: HashMap<String, Vec<(usize, usize)>>,
+    pub settings: Settings,
+    pub bindings: InputBindings,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        SaveData {
+            version: SAVE_VERSION,
+            unlocked_levels: 1,
+            best_times: HashMap::new(),
+            collectibles: HashMap::new(),
+            settings: Settings::default(),
+            bindings: InputBindings::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub camera_shake: bool,
+    pub camera_zoom: bool,
+    /// Volumes between 0.0 and 1.0
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// Identifier of the player skin
+    pub skin: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            camera_shake: true,
+            camera_zoom: true,
+            master_volume: 1.0,
+            music_volume: 0.6,
+            sfx_volume: 0.8,
+            skin: SKINS[0].id.to_string(),
+        }
+    }
+}
+
+/// Only read the version, to know how to parse the rest of the save
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load());
+        app.add_systems(PreUpdate, apply_save.run_if(resource_changed::<SaveData>));
+        app.add_systems(Update, unlock_next_level);
+        app.add_systems(Last, write_save.run_if(resource_changed::<SaveData>));
+    }
+}
+
+/// Apply the saved settings and bindings to the game resources
+fn apply_save(
+    save: Res<SaveData>,
+    mut bindings: ResMut<InputBindings>,
+    mut camera_settings: ResMut<CameraEffectsSettings>,
+    mut audio_settings: ResMut<AudioSettings>,
+) {
+    *bindings = save.bindings.clone();
+    camera_settings.shake = save.settings.camera_shake;
+    camera_settings.zoom = save.settings.camera_zoom;
+    audio_settings.master = save.settings.master_volume.clamp(0.0, 1.0);
+    audio_settings.music = save.settings.music_volume.clamp(0.0, 1.0);
+    audio_settings.sfx = save.settings.sfx_volume.clamp(0.0, 1.0);
+}
+
+fn unlock_next_level(
+    mut finished_event: EventReader<LevelFinished>,
+    current_level: Res<CurrentLevel>,
+    mut save: ResMut<SaveData>,
+) {
+    if finished_event.is_empty() {
+        return;
+    }
+    finished_event.clear();
+
+    let unlocked = (current_level.0 + 2).min(LEVELS.len());
+    if save.unlocked_levels < unlocked {
+        save.unlocked_levels = unlocked;
+    }
+}
+
+fn write_save(save: Res<SaveData>) {
+    // Nothing new to store right after loading
+    if save.is_added() {
+        return;
+    }
+
+    match ron::ser::to_string_pretty(&*save, ron::ser::PrettyConfig::default()) {
+        Ok(content) => storage::write(&content),
+        Err(error) => warn!("Could not serialize the save: {}", error),
+    }
+}
+
+fn load() -> SaveData {
+    if let Some(content) = storage::read() {
+        match parse(&content) {
+            Ok(save) => return save,
+            Err(error) => warn!("Could not read the save, starting a new one: {}", error),
+        }
+    } else if let Some(content) = storage::read_legacy() {
+        info!("Migrating personal bests from save version 1");
+        return migrate_v1(&content);
+    }
+
+    SaveData::default()
+}
+
+fn parse(content: &str) -> Result<SaveData, ron::error::SpannedError> {
+    let header: SaveHeader = ron::from_str(content)?;
+    if header.version > SAVE_VERSION {
+        warn!("Save version {} is newer than the game, some data may be lost", header.version);
+    }
+
+    // Fields added since the save was written get their default value
+    let mut save: SaveData = ron::from_str(content)?;
+    save.version = SAVE_VERSION;
+    Ok(save)
+}
+
+/// Version 1 stored one "level_id milliseconds" entry per line
+fn migrate_v1(content: &str) -> SaveData {
+    let mut save = SaveData::default();
+
+    for line in content.lines() {
+        if let Some((id, millis)) = line.split_once(' ') {
+            if let Ok(millis) = millis.trim().parse::<u64>() {
+                save.best_times.insert(id.to_string(), Duration::from_millis(millis));
+            }
+        }
+    }
+
+    save
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::path::PathBuf;
+
+    use bevy::log::warn;
+
+    const LEGACY_FILE: &str = "personal_bests.txt";
+
+    fn save_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("pl-art-former").join("save.ron"))
+    }
+
+    pub fn read() -> Option<String> {
+        std::fs::read_to_string(save_path()?).ok()
+    }
+
+    pub fn read_legacy() -> Option<String> {
+        std::fs::read_to_string(LEGACY_FILE).ok()
+    }
+
+    pub fn write(content: &str) {
+        let Some(path) = save_path() else {
+            warn!("No data directory to write the save");
+            return;
+        };
+
+        if let Some(dir) = path.parent() {
+            if let Err(error) = std::fs::create_dir_all(dir) {
+                warn!("Could not create the save directory: {}", error);
+                return;
+            }
+        }
+        if let Err(error) = std::fs::write(path, content) {
+            warn!("Could not write the save: {}", error);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    use bevy::log::warn;
+
+    const SAVE_KEY: &str = "pl-art-former-save";
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn read() -> Option<String> {
+        local_storage()?.get_item(SAVE_KEY).ok()?
+    }
+
+    // Version 1 was never persisted on the web
+    pub fn read_legacy() -> Option<String> {
+        None
+    }
+
+    pub fn write(content: &str) {
+        let Some(storage) = local_storage() else {
+            warn!("No local storage to write the save");
+            return;
+        };
+
+        if storage.set_item(SAVE_KEY, content).is_err() {
+            warn!("Could not write the save");
+        }
+    }
+}
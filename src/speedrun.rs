@@ -0,0 +1,182 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Speedrun timer, with a split per level and personal bests.
+/// The level timer starts on the first input after the level is loaded and
+/// stops when the player reaches the finish.
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::ApplicationState;
+use crate::player::controller::{Action, ActionEvent, Controller};
+use crate::save::SaveData;
+use crate::world::{levels::LEVELS, finish::{finish_detection, LevelFinished}, CurrentLevel};
+
+pub struct SpeedrunPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum TimerState {
+    #[default] WaitingForInput,
+    Running,
+    Stopped,
+}
+
+#[derive(Resource, Default)]
+pub struct SpeedrunTimer {
+    state: TimerState,
+    level_time: Duration,
+    /// Time of each level finished during the current run
+    splits: Vec<(&'static str, Duration)>,
+}
+
+impl SpeedrunTimer {
+    fn run_time(&self) -> Duration {
+        self.splits.iter().map(|(_, time)| *time).sum::<Duration>() + self.level_time
+    }
+}
+
+#[derive(Component)]
+struct SpeedrunOverlay;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeedrunTimer>();
+        app.add_systems(Startup, overlay_setup);
+        app.add_systems(OnEnter(ApplicationState::Title), reset_run);
+        app.add_systems(Update, reset_level_timer.run_if(resource_changed::<CurrentLevel>));
+        app.add_systems(
+            Update,
+            (start_timer, tick_timer, stop_timer.after(finish_detection))
+                .chain()
+                .after(reset_level_timer)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(Update, (toggle_overlay, update_overlay));
+    }
+}
+
+fn reset_run(mut timer: ResMut<SpeedrunTimer>) {
+    timer.splits.clear();
+}
+
+fn reset_level_timer(mut timer: ResMut<SpeedrunTimer>) {
+    timer.state = TimerState::WaitingForInput;
+    timer.level_time = Duration::ZERO;
+}
+
+fn start_timer(
+    mut timer: ResMut<SpeedrunTimer>,
+    mut action_event: EventReader<ActionEvent>,
+    controller_query: Query<&Controller>,
+) {
+    let any_action = action_event.read().any(|event| event.0 != Action::None);
+    let any_direction = controller_query.iter().any(|controller| controller.direction != Vec2::ZERO);
+
+    if timer.state == TimerState::WaitingForInput && (any_action || any_direction) {
+        debug!("Speedrun timer started");
+        timer.state = TimerState::Running;
+    }
+}
+
+fn tick_timer(mut timer: ResMut<SpeedrunTimer>, time: Res<Time>) {
+    if timer.state == TimerState::Running {
+        timer.level_time += time.delta();
+    }
+}
+
+pub(crate) fn stop_timer(
+    mut timer: ResMut<SpeedrunTimer>,
+    mut finished_event: EventReader<LevelFinished>,
+    mut save: ResMut<SaveData>,
+    current_level: Res<CurrentLevel>,
+) {
+    if finished_event.is_empty() {
+        return;
+    }
+    finished_event.clear();
+
+    if timer.state != TimerState::Running {
+        return;
+    }
+
+    let level_id = LEVELS[current_level.0].id;
+    let level_time = timer.level_time;
+    timer.state = TimerState::Stopped;
+    timer.splits.push((level_id, level_time));
+    timer.level_time = Duration::ZERO;
+    info!("Level {} finished in {}", level_id, format_time(level_time));
+
+    let is_best = save
+        .best_times
+        .get(level_id)
+        .map_or(true, |best| level_time < *best);
+    if is_best {
+        info!("New personal best on {}", level_id);
+        save.best_times.insert(level_id.to_string(), level_time);
+    }
+}
+
+fn overlay_setup(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        }),
+        SpeedrunOverlay,
+    ));
+}
+
+fn toggle_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Visibility, With<SpeedrunOverlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyT) {
+        for mut visibility in query.iter_mut() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+fn update_overlay(
+    timer: Res<SpeedrunTimer>,
+    save: Res<SaveData>,
+    current_level: Res<CurrentLevel>,
+    mut query: Query<&mut Text, With<SpeedrunOverlay>>,
+) {
+    let level_id = LEVELS[current_level.0].id;
+    let mut text = format!("{}  {}\n", level_id, format_time(timer.level_time));
+
+    if let Some(best) = save.best_times.get(level_id) {
+        text += &format!("PB  {}\n", format_time(*best));
+    }
+    for (id, split) in timer.splits.iter() {
+        text += &format!("{}  {}\n", id, format_time(*split));
+    }
+    if !timer.splits.is_empty() {
+        text += &format!("Total  {}", format_time(timer.run_time()));
+    }
+
+    for mut overlay in query.iter_mut() {
+        overlay.sections[0].value = text.clone();
+    }
+}
+
+fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{:02}:{:02}.{:03}", millis / 60_000, (millis / 1000) % 60, millis % 1000)
+}
@@ -0,0 +1,218 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Items to collect in the levels, placed with a '*' in the level map.
+/// The collected items are stored per level in the save once the player
+/// reaches a checkpoint or the finish, the ones picked since are back after a
+/// death. The items collected in a previous run are displayed faded.
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_particle_systems::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::Player;
+use crate::save::SaveData;
+use crate::world::*;
+
+const COLLECTIBLE_COLOR: Color = Color::rgb(1.0, 0.9, 0.3);
+const COLLECTED_COLOR: Color = Color::rgba(1.0, 0.9, 0.3, 0.3);
+const COLLECTIBLE_SIZE: f32 = TILE_SCALED / 2.0;
+const BOB_HEIGHT: f32 = 4.0;
+const BOB_SPEED: f32 = 3.0;
+
+#[derive(Component)]
+pub struct Collectible {
+    /// Position in the level map, used as identifier in the save
+    pub grid: (usize, usize),
+    origin: Vec3,
+}
+
+/// Collected and total number of items in the current level
+#[derive(Resource, Default)]
+pub struct LevelCollectibles {
+    pub collected: usize,
+    pub total: usize,
+    /// Items picked since the last checkpoint, not in the save yet
+    pending: Vec<(usize, usize)>,
+}
+
+#[derive(Component)]
+pub struct CollectiblesCounter;
+
+fn collectible_collider() -> Collider {
+    Collider::cuboid(COLLECTIBLE_SIZE / 2.0, COLLECTIBLE_SIZE / 2.0)
+}
+
+pub fn spawn_collectible(commands: &mut Commands, translation: Vec3, grid: (usize, usize), collected: bool) {
+    let mut entity = commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color: if collected { COLLECTED_COLOR } else { COLLECTIBLE_COLOR },
+            custom_size: Some(Vec2::splat(COLLECTIBLE_SIZE)),
+            ..default()
+        },
+        transform: Transform::from_translation(translation)
+            .with_rotation(Quat::from_rotation_z(PI / 4.0)),
+        ..default()
+    });
+    entity
+        .insert(Collectible { grid, origin: translation })
+        .insert(LevelEntity);
+
+    // Items already collected are only shown, not picked up again
+    if !collected {
+        entity
+            .insert(collectible_collider())
+            .insert(Sensor)
+            .insert(ActiveEvents::COLLISION_EVENTS);
+    }
+}
+
+pub fn setup_counter(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        }),
+        CollectiblesCounter,
+    ));
+}
+
+pub fn animate_collectibles(time: Res<Time>, mut query: Query<(&Collectible, &mut Transform)>) {
+    let elapsed = time.elapsed_seconds();
+    for (collectible, mut transform) in query.iter_mut() {
+        // Offset the phase with the position, so the items don't move in sync
+        let phase = collectible.origin.x * 0.1;
+        transform.translation.y = collectible.origin.y + BOB_HEIGHT * (elapsed * BOB_SPEED + phase).sin();
+        transform.rotation = Quat::from_rotation_z(PI / 4.0 + (elapsed * BOB_SPEED + phase).sin() * 0.3);
+    }
+}
+
+pub fn collect(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    mut collectible_query: Query<(&Collectible, &Transform, &mut Visibility)>,
+    mut level_collectibles: ResMut<LevelCollectibles>,
+) {
+    let player = player_query.single();
+
+    for event in collision_events.read() {
+        let Some(other) = player_contact(event, player) else {
+            continue;
+        };
+        let Ok((collectible, transform, mut visibility)) = collectible_query.get_mut(other) else {
+            continue;
+        };
+
+        info!("Collected item at {:?}", collectible.grid);
+        level_collectibles.collected += 1;
+        level_collectibles.pending.push(collectible.grid);
+
+        spawn_pickup_burst(&mut commands, transform.translation);
+        // Hidden rather than despawned, to come back if the player dies
+        *visibility = Visibility::Hidden;
+        commands.entity(other).remove::<Collider>();
+    }
+}
+
+/// Store the pending items in the save when reaching a checkpoint or the finish
+pub fn save_collectibles(
+    mut finished_event: EventReader<LevelFinished>,
+    spawn_point: Res<SpawnPoint>,
+    current_level: Res<CurrentLevel>,
+    mut level_collectibles: ResMut<LevelCollectibles>,
+    mut save: ResMut<SaveData>,
+) {
+    let finished = !finished_event.is_empty();
+    finished_event.clear();
+
+    // The spawn point only changes in a level when reaching a checkpoint
+    if !(finished || spawn_point.is_changed()) || level_collectibles.pending.is_empty() {
+        return;
+    }
+
+    save.collectibles
+        .entry(LEVELS[current_level.0].id.to_string())
+        .or_default()
+        .append(&mut level_collectibles.pending);
+}
+
+/// Put back the items picked since the last checkpoint
+pub fn restore_collectibles(
+    mut commands: Commands,
+    mut died_event: EventReader<PlayerDied>,
+    mut level_collectibles: ResMut<LevelCollectibles>,
+    mut query: Query<(Entity, &Collectible, &mut Visibility)>,
+) {
+    if died_event.is_empty() {
+        return;
+    }
+    died_event.clear();
+    if level_collectibles.pending.is_empty() {
+        return;
+    }
+
+    for (entity, collectible, mut visibility) in query.iter_mut() {
+        if level_collectibles.pending.contains(&collectible.grid) {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).insert(collectible_collider());
+        }
+    }
+    level_collectibles.collected -= level_collectibles.pending.len();
+    level_collectibles.pending.clear();
+}
+
+fn spawn_pickup_burst(commands: &mut Commands, translation: Vec3) {
+    commands
+        .spawn(ParticleSystemBundle {
+            particle_system: ParticleSystem {
+                max_particles: 20,
+                emitter_shape: EmitterShape::Line(Line {
+                    length: COLLECTIBLE_SIZE / 2.0,
+                    angle: JitteredValue::jittered(0.0, -PI..PI),
+                }),
+                spawn_rate_per_second: 0.0.into(),
+                bursts: vec![ParticleBurst { time: 0.0, count: 20 }],
+                initial_speed: JitteredValue::jittered(80.0, -20.0..40.0),
+                lifetime: JitteredValue::jittered(0.4, -0.1..0.1),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(COLLECTIBLE_COLOR, 0.0),
+                    CurvePoint::new(Color::rgba(1.0, 1.0, 1.0, 0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(3.0, -1.0..1.0),
+                looping: false,
+                system_duration_seconds: 0.5,
+                despawn_on_finish: true,
+                ..ParticleSystem::default()
+            },
+            transform: Transform::from_translation(translation),
+            ..ParticleSystemBundle::default()
+        })
+        .insert(Playing);
+}
+
+pub fn update_counter(
+    level_collectibles: Res<LevelCollectibles>,
+    mut query: Query<(&mut Text, &mut Visibility), With<CollectiblesCounter>>,
+) {
+    for (mut text, mut visibility) in query.iter_mut() {
+        if level_collectibles.total == 0 {
+            *visibility = Visibility::Hidden;
+        } else {
+            *visibility = Visibility::Inherited;
+            text.sections[0].value = format!("* {}/{}", level_collectibles.collected, level_collectibles.total);
+        }
+    }
+}
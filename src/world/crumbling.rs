@@ -0,0 +1,173 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Blocks breaking a short time after the player stands on or holds to
+/// them, placed with a 'x' in the level map, or a 'X' for a block with holds.
+/// The blocks come back after a delay when the level defines one.
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_particle_systems::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::{Player, Support};
+use crate::world::*;
+
+const CRUMBLING_COLOR: Color = Color::rgb(0.9, 0.8, 0.7);
+const CRUMBLE_DELAY: f32 = 0.5;
+// Maximal offset of the block while shaking
+const SHAKE_AMPLITUDE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CrumbleState {
+    Solid,
+    Shaking,
+    Broken,
+}
+
+#[derive(Component)]
+pub struct Crumbling {
+    state: CrumbleState,
+    timer: Timer,
+    origin: Vec3,
+    /// Delay before the block comes back, never when `None`
+    respawn: Option<f32>,
+}
+
+impl Crumbling {
+    /// The block is in place, shaking or not
+    pub fn is_solid(&self) -> bool {
+        self.state != CrumbleState::Broken
+    }
+}
+
+pub fn spawn_crumbling(
+    commands: &mut Commands,
+    tile_set: &TileSet,
+    atlas_index: usize,
+    translation: Vec3,
+    with_holds: bool,
+    respawn: Option<f32>,
+) {
+    let mut entity = commands.spawn(SpriteSheetBundle {
+        sprite: Sprite {
+            color: CRUMBLING_COLOR,
+            ..default()
+        },
+        texture: tile_set.texture.clone(),
+        atlas: TextureAtlas {
+            layout: tile_set.layout.clone(),
+            index: atlas_index,
+        },
+        transform: Transform {
+            translation,
+            scale: Vec3::new(TILE_SCALER, TILE_SCALER, 0.0),
+            ..default()
+        },
+        ..default()
+    });
+    entity
+        .insert(Crumbling {
+            state: CrumbleState::Solid,
+            timer: Timer::from_seconds(CRUMBLE_DELAY, TimerMode::Once),
+            origin: translation,
+            respawn,
+        })
+        .insert(LevelEntity)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(TILE_SIZE / 2.0, TILE_SIZE / 2.0));
+
+    if with_holds {
+        entity.insert(WallHold { key: rand::random(), wall_index: atlas_index });
+    }
+}
+
+/// Start shaking the blocks detected under or in front of the player
+pub fn trigger_crumbling(
+    support_query: Query<&Support, With<Player>>,
+    mut query: Query<&mut Crumbling>,
+) {
+    let support = support_query.single();
+
+    for entity in [support.ground, support.wall].into_iter().flatten() {
+        if let Ok(mut crumbling) = query.get_mut(entity) {
+            if crumbling.state == CrumbleState::Solid {
+                debug!("Block crumbling {:?}", entity);
+                crumbling.state = CrumbleState::Shaking;
+                crumbling.timer = Timer::from_seconds(CRUMBLE_DELAY, TimerMode::Once);
+            }
+        }
+    }
+}
+
+pub fn update_crumbling(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Crumbling, &mut Transform, &mut Visibility)>,
+    time: Res<Time>,
+    level_theme: Res<LevelTheme>,
+) {
+    for (entity, mut crumbling, mut transform, mut visibility) in query.iter_mut() {
+        crumbling.timer.tick(time.delta());
+
+        match crumbling.state {
+            CrumbleState::Solid => {}
+            CrumbleState::Shaking => {
+                if crumbling.timer.finished() {
+                    transform.translation = crumbling.origin;
+                    *visibility = Visibility::Hidden;
+                    commands.entity(entity).remove::<Collider>();
+                    spawn_crumble_burst(&mut commands, crumbling.origin, level_theme.0.dust_color);
+
+                    crumbling.state = CrumbleState::Broken;
+                    if let Some(delay) = crumbling.respawn {
+                        crumbling.timer = Timer::from_seconds(delay, TimerMode::Once);
+                    }
+                } else {
+                    let offset = Vec2::new(rand::random::<f32>(), rand::random::<f32>()) * 2.0 - 1.0;
+                    transform.translation = crumbling.origin + (offset * SHAKE_AMPLITUDE).extend(0.0);
+                }
+            }
+            CrumbleState::Broken => {
+                if crumbling.respawn.is_some() && crumbling.timer.finished() {
+                    debug!("Block back {:?}", entity);
+                    *visibility = Visibility::Inherited;
+                    commands
+                        .entity(entity)
+                        .insert(Collider::cuboid(TILE_SIZE / 2.0, TILE_SIZE / 2.0));
+                    crumbling.state = CrumbleState::Solid;
+                }
+            }
+        }
+    }
+}
+
+fn spawn_crumble_burst(commands: &mut Commands, translation: Vec3, color: Color) {
+    commands
+        .spawn(ParticleSystemBundle {
+            particle_system: ParticleSystem {
+                max_particles: 16,
+                emitter_shape: EmitterShape::Line(Line {
+                    length: TILE_SCALED,
+                    // Falling debris
+                    angle: JitteredValue::jittered(-PI / 2.0, -1.0..1.0),
+                }),
+                spawn_rate_per_second: 0.0.into(),
+                bursts: vec![ParticleBurst { time: 0.0, count: 16 }],
+                initial_speed: JitteredValue::jittered(60.0, -20.0..20.0),
+                lifetime: JitteredValue::jittered(0.5, -0.2..0.2),
+                color: ColorOverTime::Gradient(Curve::new(vec![
+                    CurvePoint::new(color, 0.0),
+                    CurvePoint::new(color.with_a(0.0), 1.0),
+                ])),
+                initial_scale: JitteredValue::jittered(4.0, -1.0..1.0),
+                looping: false,
+                system_duration_seconds: 0.6,
+                despawn_on_finish: true,
+                ..ParticleSystem::default()
+            },
+            transform: Transform::from_translation(translation),
+            ..ParticleSystemBundle::default()
+        })
+        .insert(Playing);
+}
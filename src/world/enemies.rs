@@ -0,0 +1,200 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Creatures moving in the levels, defined in the level data.
+/// Every enemy walks or flies between the walls, the behaviours are added as
+/// extra components: `Chase` follows the player when close enough and
+/// `SineFly` waves up and down. Touching an enemy sends the player back to
+/// the level start, unless landing on it from above.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::Player;
+use crate::player::movement::JUMP_SPEED;
+use crate::world::springs::Launched;
+use crate::world::*;
+use crate::RestartEvent;
+
+const ENEMY_SIZE: f32 = TILE_SCALED * 0.75;
+const WALKER_COLOR: Color = Color::rgb(0.6, 0.2, 0.6);
+const CHASER_COLOR: Color = Color::rgb(0.9, 0.3, 0.2);
+const FLYER_COLOR: Color = Color::rgb(0.3, 0.5, 0.9);
+
+const WALKER_SPEED: f32 = 60.0;
+const CHASER_SPEED: f32 = 110.0;
+const FLYER_SPEED: f32 = 80.0;
+const CHASE_RANGE: f32 = 6.0 * TILE_SCALED;
+const FLY_AMPLITUDE: f32 = TILE_SCALED;
+const FLY_FREQUENCY: f32 = 2.0;
+
+// Bounce given to the player when stomping an enemy
+const STOMP_SPEED: f32 = 0.8 * JUMP_SPEED;
+const STOMP_INHIBITION: Duration = Duration::from_millis(50);
+
+#[derive(Component)]
+pub struct Enemy {
+    speed: f32,
+    /// -1.0 going left, 1.0 going right
+    direction: f32,
+    /// Turn back at the edge of the platforms
+    walking: bool,
+}
+
+/// Go toward the player when in range
+#[derive(Component)]
+pub struct Chase {
+    range: f32,
+    chasing: bool,
+}
+
+/// Wave up and down around the spawn height
+#[derive(Component)]
+pub struct SineFly {
+    amplitude: f32,
+    frequency: f32,
+}
+
+pub fn spawn_enemy(commands: &mut Commands, definition: &EnemyDefinition) {
+    let (x, y) = definition.start;
+    let mut translation = grid_translation(x, y);
+
+    let (color, speed, walking) = match definition.kind {
+        EnemyKind::Walker => (WALKER_COLOR, WALKER_SPEED, true),
+        EnemyKind::Chaser => (CHASER_COLOR, CHASER_SPEED, true),
+        EnemyKind::Flyer => (FLYER_COLOR, FLYER_SPEED, false),
+    };
+    // Walking enemies stand on the bottom of their tile
+    if walking {
+        translation.y -= (TILE_SCALED - ENEMY_SIZE) / 2.0;
+    }
+
+    let mut entity = commands.spawn(SpriteBundle {
+        sprite: Sprite {
+            color,
+            custom_size: Some(Vec2::splat(ENEMY_SIZE)),
+            ..default()
+        },
+        transform: Transform::from_translation(translation),
+        ..default()
+    });
+    entity
+        .insert(Enemy { speed, direction: 1.0, walking })
+        .insert(LevelEntity)
+        .insert(RigidBody::KinematicVelocityBased)
+        .insert(Velocity::zero())
+        .insert(Collider::cuboid(ENEMY_SIZE / 2.0, ENEMY_SIZE / 2.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS);
+
+    match definition.kind {
+        EnemyKind::Walker => {}
+        EnemyKind::Chaser => {
+            entity.insert(Chase { range: CHASE_RANGE, chasing: false });
+        }
+        EnemyKind::Flyer => {
+            entity.insert(SineFly { amplitude: FLY_AMPLITUDE, frequency: FLY_FREQUENCY });
+        }
+    }
+}
+
+pub fn chase_player(
+    player_query: Query<&Transform, With<Player>>,
+    mut query: Query<(&Transform, &mut Enemy, &mut Chase), Without<Player>>,
+) {
+    let player = player_query.single();
+
+    for (transform, mut enemy, mut chase) in query.iter_mut() {
+        let distance = player.translation.truncate() - transform.translation.truncate();
+        chase.chasing = distance.length() < chase.range;
+
+        // Out of range, keep patrolling in the last direction
+        if chase.chasing {
+            enemy.direction = distance.x.signum();
+        }
+    }
+}
+
+/// Move the enemies horizontally, turning back in front of a wall or a gap.
+/// Chasing enemies wait at the obstacle instead.
+#[allow(clippy::type_complexity)]
+pub fn move_enemies(
+    mut query: Query<(Entity, &Transform, &mut Enemy, &mut Velocity, &mut Sprite, Option<&Chase>)>,
+    player_query: Query<Entity, With<Player>>,
+    rapier_ctx: Res<RapierContext>,
+) {
+    let player = player_query.single();
+
+    for (entity, transform, mut enemy, mut velocity, mut sprite, chase) in query.iter_mut() {
+        let filter = QueryFilter::default()
+            .exclude_rigid_body(entity)
+            .exclude_rigid_body(player)
+            .exclude_sensors();
+        let position = transform.translation.truncate();
+        let ahead = Vec2::new(enemy.direction, 0.0);
+
+        let wall_ahead = rapier_ctx
+            .cast_ray(position, ahead, ENEMY_SIZE / 2.0 + 2.0, true, filter)
+            .is_some();
+        let gap_ahead = enemy.walking
+            && rapier_ctx
+                .cast_ray(position + ahead * ENEMY_SIZE, Vec2::NEG_Y, ENEMY_SIZE, true, filter)
+                .is_none();
+
+        let chasing = chase.is_some_and(|chase| chase.chasing);
+        if wall_ahead || gap_ahead {
+            if chasing {
+                velocity.linvel.x = 0.0;
+                continue;
+            }
+            enemy.direction = -enemy.direction;
+        }
+
+        velocity.linvel.x = enemy.direction * enemy.speed;
+        sprite.flip_x = enemy.direction < 0.0;
+    }
+}
+
+pub fn fly_enemies(time: Res<Time>, mut query: Query<(&SineFly, &mut Velocity)>) {
+    let elapsed = time.elapsed_seconds();
+    for (fly, mut velocity) in query.iter_mut() {
+        // Derivative of the height, to stay on the sine around the spawn height
+        velocity.linvel.y = fly.amplitude * fly.frequency * (elapsed * fly.frequency).cos();
+    }
+}
+
+/// Stomp the enemies landed on, any other contact restarts the level
+pub fn enemy_contact(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<(Entity, &Transform, &Velocity), With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    mut launched_event: EventWriter<Launched>,
+    mut restart_event: EventWriter<RestartEvent>,
+) {
+    let (player, player_transform, player_velocity) = player_query.single();
+
+    for event in collision_events.read() {
+        let Some(other) = player_contact(event, player) else {
+            continue;
+        };
+        let Ok(enemy_transform) = enemy_query.get(other) else {
+            continue;
+        };
+
+        let above = player_transform.translation.y > enemy_transform.translation.y + ENEMY_SIZE / 4.0;
+        if above && player_velocity.linvel.y < 0.0 {
+            info!("Enemy stomped");
+            commands.entity(other).despawn_recursive();
+            launched_event.send(Launched {
+                velocity: Vec2::new(player_velocity.linvel.x, STOMP_SPEED),
+                inhibition: STOMP_INHIBITION,
+            });
+        } else {
+            info!("Player hit by an enemy");
+            restart_event.send_default();
+        }
+    }
+}
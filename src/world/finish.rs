@@ -0,0 +1,55 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// End of level marker, placed with a 'F' in the level map
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::Player;
+use crate::world::*;
+
+const FINISH_COLOR: Color = Color::rgb(1.0, 0.84, 0.0);
+
+#[derive(Component)]
+pub struct Finish;
+
+/// Inform other system that the player reached the end of the level
+#[derive(Event, Default)]
+pub struct LevelFinished;
+
+pub fn spawn_finish(commands: &mut Commands, translation: Vec3) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: FINISH_COLOR,
+                custom_size: Some(Vec2::new(TILE_SCALED / 2.0, TILE_SCALED)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(Finish)
+        .insert(LevelEntity)
+        .insert(Collider::cuboid(TILE_SCALED / 4.0, TILE_SCALED / 2.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS);
+}
+
+pub fn finish_detection(
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    finish_query: Query<(), With<Finish>>,
+    mut finished_event: EventWriter<LevelFinished>,
+) {
+    let player = player_query.single();
+
+    for event in collision_events.read() {
+        if let Some(other) = player_contact(event, player) {
+            if finish_query.contains(other) {
+                info!("Level finished");
+                finished_event.send_default();
+            }
+        }
+    }
+}
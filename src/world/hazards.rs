@@ -0,0 +1,128 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Elements killing the player and checkpoints where the player respawns.
+/// Spikes are placed with a '^' and checkpoints with a 'C' in the level map.
+/// A kill-plane is added below every level to catch falling players.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::Player;
+use crate::world::*;
+
+const SPIKES_COLOR: Color = Color::rgb(0.8, 0.1, 0.1);
+const CHECKPOINT_COLOR: Color = Color::rgb(0.4, 0.4, 0.5);
+const CHECKPOINT_ACTIVE_COLOR: Color = Color::rgb(0.2, 0.9, 0.9);
+
+// Distance below the level bounds where the player dies
+const KILL_PLANE_DEPTH: f32 = 4.0 * TILE_SCALED;
+
+#[derive(Component)]
+pub struct Hazard;
+
+#[derive(Component)]
+pub struct Checkpoint;
+
+/// Inform other system that the player died and must respawn
+#[derive(Event, Default)]
+pub struct PlayerDied;
+
+pub fn spawn_spikes(commands: &mut Commands, translation: Vec3) {
+    // Only the lower half of the tile is deadly
+    let translation = translation - Vec3::new(0.0, TILE_SCALED / 4.0, 0.0);
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: SPIKES_COLOR,
+                custom_size: Some(Vec2::new(TILE_SCALED, TILE_SCALED / 2.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(Hazard)
+        .insert(LevelEntity)
+        .insert(Collider::cuboid(TILE_SCALED / 2.0, TILE_SCALED / 4.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS);
+}
+
+pub fn spawn_kill_plane(commands: &mut Commands, bounds: Rect) {
+    let half_width = bounds.half_size().x + KILL_PLANE_DEPTH;
+    commands
+        .spawn(TransformBundle::from_transform(Transform::from_xyz(
+            bounds.center().x,
+            bounds.min.y - KILL_PLANE_DEPTH,
+            0.0,
+        )))
+        .insert(Hazard)
+        .insert(LevelEntity)
+        .insert(Collider::cuboid(half_width, TILE_SCALED / 2.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS);
+}
+
+pub fn spawn_checkpoint(commands: &mut Commands, translation: Vec3) {
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: CHECKPOINT_COLOR,
+                custom_size: Some(Vec2::new(TILE_SCALED / 4.0, TILE_SCALED)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(Checkpoint)
+        .insert(LevelEntity)
+        .insert(Collider::cuboid(TILE_SCALED / 2.0, TILE_SCALED / 2.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS);
+}
+
+pub fn hazard_detection(
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    hazard_query: Query<(), With<Hazard>>,
+    mut died_event: EventWriter<PlayerDied>,
+) {
+    let player = player_query.single();
+
+    for event in collision_events.read() {
+        if let Some(other) = player_contact(event, player) {
+            if hazard_query.contains(other) {
+                info!("Player died");
+                died_event.send_default();
+            }
+        }
+    }
+}
+
+pub fn checkpoint_detection(
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    mut checkpoint_query: Query<(Entity, &Transform, &mut Sprite), With<Checkpoint>>,
+    mut spawn_point: ResMut<SpawnPoint>,
+) {
+    let player = player_query.single();
+
+    for event in collision_events.read() {
+        let Some(other) = player_contact(event, player) else {
+            continue;
+        };
+        if !checkpoint_query.contains(other) {
+            continue;
+        }
+
+        info!("Checkpoint reached");
+        for (entity, transform, mut sprite) in checkpoint_query.iter_mut() {
+            if entity == other {
+                spawn_point.0 = transform.translation.truncate();
+                sprite.color = CHECKPOINT_ACTIVE_COLOR;
+            } else {
+                sprite.color = CHECKPOINT_COLOR;
+            }
+        }
+    }
+}
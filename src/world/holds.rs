@@ -7,7 +7,7 @@ use bevy::prelude::*;
 use crate::player::controller::*;
 use crate::world::*;
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HoldKey {
     A,
     S,
@@ -44,26 +44,30 @@ pub enum HoldsVisibility {
 #[derive(Event, Default)]
 pub struct ShowHolds(pub HoldsVisibility);
 
+/// The holds of a moving platform are shown on the tiles of the platform
 pub fn show_hold(
     mut show_event: EventReader<ShowHolds>,
-    mut wall_hold_query: Query<(&mut WallHold, &mut TextureAtlas)>
+    wall_hold_query: Query<(Entity, &WallHold, Option<&Children>)>,
+    mut atlas_query: Query<&mut TextureAtlas>,
 ) {
     if !show_event.is_empty(){
         info!("Got new showhold event");
         for event in show_event.read() {
-            match event.0 {
-                HoldsVisibility::Hidden => {
-                    for (mut hold, mut atlas) in wall_hold_query.iter_mut() {
-                        atlas.index = hold.wall_index;
-                    }
-                },
-                HoldsVisibility::Visible => {
-                    info!("Displaying holds");
-                    for (mut hold, mut atlas) in wall_hold_query.iter_mut() {
-                        atlas.index = 6*4+(hold.key as usize);
+            if event.0 == HoldsVisibility::Visible {
+                info!("Displaying holds");
+            }
+            for (entity, hold, children) in wall_hold_query.iter() {
+                let index = match event.0 {
+                    HoldsVisibility::Hidden => hold.wall_index,
+                    HoldsVisibility::Visible => 6*4+(hold.key as usize),
+                };
+                let tiles = std::iter::once(entity).chain(children.into_iter().flatten().copied());
+                for tile in tiles {
+                    if let Ok(mut atlas) = atlas_query.get_mut(tile) {
+                        atlas.index = index;
                     }
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -2,6 +2,10 @@
  * Copyright (c) 2024 Louis Mayencourt
  */
 
+use bevy::prelude::*;
+
+use crate::world::lighting::LightingDefinition;
+
 /// Embedded levels map as string.
 /// This allow an easy WASM deployment, as no external assets is needed to
 /// store the levels.
@@ -11,26 +15,26 @@ DDDDDDDDDDDDDDDDDDDDDDDDDDDDDDDD
 D..............................D
 D..............................D
 D..............................D
-D..............................D
+D......*.......................D
 D.....BBbb.....................D
-D..............................D
+D....................*.........D
 D..................RrrrRRR.....D
-D........................R.....D
-D........................R.....D
+D..................xxx...R.....D
+D..............*.........R.....D
 D............dddd........R.....D
-D........................R.....D
-D........r...............R.....D
+D.....*..................X.....D
+D........r....----.......X.....D
 D....rrrrr.....................D
 D...............................
-D...............................
+D..S.........................>...C...^^^.....F..
 RRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRRGGGGGGGGGGGGGGGGGGG
 ggggggggggggggggggggggggggggggggggggggggggggggggggg
 ";
 
 pub const LEVEL_GENERATED: &str = "
 BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB
-B....BBB..BB......B...................B.
-.....BBB..BB.........BBB................
+B....BBB..BB......B...................BF
+.....BBB..BB.........BBB......*.........
 ....BBBB..BBBBBB.....BBB...BBBBBBBB.....
 B...BBBB..BBBBBB......BB...BBBBBBBBBBBB.
 B..BBBBB..BBBBBBB..........BBBBBBBBBBBB.
@@ -45,7 +49,7 @@ B...BB..BBBBBB....BB..BBBBBBBBBBBBBBBBB.
 ....BB..BBBBBB....BBBBBBBBBBBBBBBB..BB..
 ...BBB.....BBBBB..BBBBBB..BBBBBB....BB..
 ...BBB.....BBBBB..BB..BB..BBBBBB....BB..
-...BBB............BB................BB..
+...BBB......*.....BB................BB..
 BBBBBBBB..........BB................BBBB
 BBBBBBBB..........BB..BB...BBBB.....BBBB
 BBB..BBB......BBBBBB..BB...BBBB..BBBBBBB
@@ -58,7 +62,7 @@ B....BBBB...BBBBBBBBBBBB...BBBBBBBBBBBB.
 ..BBB...BB..BB..B......BB..BBB.......B..
 ...BB.......BB.............BBB..BB...B..
 ............BB..............BB..........
-.....................BBB....BB..........
+..........*..........BBB....BB..........
 BBBBBB...............BBB..BBBB....BB..BB
 BBBBBBBBB....B..BB...BBB..BBBBB...BB..BB
 BBBBBBBBB....B.......BBB....BBB.......BB
@@ -73,7 +77,7 @@ BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB
 ";
 
 pub const LEVEL_GENERATED_BIG: &str = "
-.BBB.......BBB.........BBBB.....BB....BB......BBB..BB....BBBBBB....BBB...BBB..BBBBBBB................BB...BBBB..........B......B
+FBBB.......BBB.........BBBB.....BB....BB......BBB..BB....BBBBBB....BBB...BBB..BBBBBBB................BB...BBBB..........B......B
 ..BBBBB....BBB.........BBBB.....BB....BBBBB...BBB..................BBB...BBB..BBBBBBBBBBBBBB.........BB...BBBBBBBBB.............
 ..BBBBBBBBBBBB....BB...BBBB..BBBBBBB..BBBBB....BB...................BBBBBBBBBBBBB..BBBBBBBBB..BBBB........BBBBBBBBB.............
 ....BBBBBBBBB.....BB.....BBBBBBBBBBB...........BBBB....BBB..........BBBBBBBBBBBBB.....BBBBBB..BBBB........BB...BB.....BBBBB.....
@@ -202,4 +206,139 @@ BBBB..BBB..BBB...............BB...BBBBB..BBB...................BBBBBBBBBB......B
 .BBB.......BBB.........BBBB.....BB....BB......BBB..BB....BBBBBB....BBB...BBB..BBBBBBB................BB...BBBB..........B......B
 ..BBBBB....BBB.........BBBB.....BB....BBBBB...BBB..................BBB...BBB..BBBBBBBBBBBBBB.........BB...BBBBBBBBB.............
 BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB
-";
\ No newline at end of file
+";
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathMode {
+    /// Go back and forth between the first and last waypoints
+    PingPong,
+    /// Go back to the first waypoint after the last one
+    Loop,
+    /// Wait for the player to step on, then travel once to the last waypoint
+    Triggered,
+}
+
+/// Platform moving along a path
+pub struct PlatformDefinition {
+    /// Position in the level map of the leftmost tile
+    pub start: (usize, usize),
+    /// Number of tiles
+    pub width: usize,
+    /// Positions to reach, in tiles relative to `start`, with y going up
+    pub waypoints: &'static [(i32, i32)],
+    pub mode: PathMode,
+    /// Speed in pixels per second
+    pub speed: f32,
+}
+
+/// Area pushing the player while inside it
+pub struct WindZoneDefinition {
+    /// Position in the level map of the top left tile
+    pub start: (usize, usize),
+    /// Width and height in tiles
+    pub size: (usize, usize),
+    /// Acceleration given to the player, in pixels per second squared
+    pub acceleration: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnemyKind {
+    /// Walk between walls and gaps
+    Walker,
+    /// Walk toward the player when close enough
+    Chaser,
+    /// Fly between walls, waving up and down
+    Flyer,
+}
+
+pub struct EnemyDefinition {
+    /// Position in the level map where the enemy appears
+    pub start: (usize, usize),
+    pub kind: EnemyKind,
+}
+
+pub struct Level {
+    pub id: &'static str,
+    pub map: &'static str,
+    /// Identifier of the visual theme in `THEMES`
+    pub theme: &'static str,
+    /// Path of the music looping during the level
+    pub music: &'static str,
+    pub platforms: &'static [PlatformDefinition],
+    pub wind_zones: &'static [WindZoneDefinition],
+    /// Delay in seconds before the crumbled blocks come back, never when `None`
+    pub crumble_respawn: Option<f32>,
+    pub enemies: &'static [EnemyDefinition],
+    /// Darkness of the level, fully lit when `None`
+    pub lighting: Option<LightingDefinition>,
+}
+
+pub const LEVELS: &[Level] = &[
+    Level {
+        id: "training",
+        map: LEVEL_TRAINING,
+        theme: "grassy_cliff",
+        music: "music/calm.wav",
+        platforms: &[
+            PlatformDefinition {
+                start: (27, 14),
+                width: 2,
+                waypoints: &[(0, 0), (0, 5)],
+                mode: PathMode::PingPong,
+                speed: 80.0,
+            },
+            PlatformDefinition {
+                start: (10, 4),
+                width: 3,
+                waypoints: &[(0, 0), (8, 0), (8, 1), (0, 1)],
+                mode: PathMode::Loop,
+                speed: 100.0,
+            },
+            PlatformDefinition {
+                start: (34, 14),
+                width: 2,
+                waypoints: &[(0, 0), (8, 0)],
+                mode: PathMode::Triggered,
+                speed: 120.0,
+            },
+        ],
+        wind_zones: &[
+            WindZoneDefinition {
+                start: (41, 9),
+                size: (3, 7),
+                acceleration: Vec2::new(0.0, 1500.0),
+            },
+        ],
+        crumble_respawn: Some(3.0),
+        enemies: &[
+            EnemyDefinition { start: (10, 16), kind: EnemyKind::Walker },
+            EnemyDefinition { start: (20, 11), kind: EnemyKind::Flyer },
+            EnemyDefinition { start: (42, 16), kind: EnemyKind::Chaser },
+        ],
+        lighting: None,
+    },
+    Level {
+        id: "generated",
+        map: LEVEL_GENERATED,
+        theme: "cave",
+        music: "music/adventure.wav",
+        platforms: &[],
+        wind_zones: &[],
+        crumble_respawn: None,
+        enemies: &[],
+        lighting: Some(LightingDefinition {
+            ambient: 0.08,
+            torches: &[(33, 2), (22, 7), (34, 18), (16, 26), (8, 30), (25, 39)],
+        }),
+    },
+    Level {
+        id: "generated_big",
+        map: LEVEL_GENERATED_BIG,
+        theme: "mountains",
+        music: "music/adventure.wav",
+        platforms: &[],
+        wind_zones: &[],
+        crumble_respawn: None,
+        enemies: &[],
+        lighting: None,
+    },
+];
@@ -0,0 +1,252 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Darkness of the levels with a `LightingDefinition`, lit by the player
+/// lantern, the torches of the level and the holds when they are shown.
+/// The light is computed on the CPU for each tile of the grid, a tile hidden
+/// from a light by a wall stays dark. The result is written in a small
+/// texture stretched over the level, so it only needs sprites and works with
+/// WebGL2.
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+
+use crate::player::Player;
+use crate::world::*;
+
+// Radius of the lights in tiles
+const LANTERN_RADIUS: f32 = 6.0;
+const TORCH_RADIUS: f32 = 4.5;
+const HOLD_RADIUS: f32 = 1.5;
+const HOLD_INTENSITY: f32 = 0.6;
+// Only the holds close to the player glow, the others are out of sight
+const HOLD_GLOW_RANGE: f32 = LANTERN_RADIUS + 4.0;
+const TORCH_FLICKER: f32 = 0.08;
+
+const TORCH_COLOR: Color = Color::rgb(1.0, 0.6, 0.2);
+const TORCH_SIZE: f32 = TILE_SCALED / 4.0;
+// Above the level and the player
+const OVERLAY_DEPTH: f32 = 10.0;
+
+/// Tiles blocking the light
+const SOLID_TILES: &str = "BbRrGgDdxX";
+
+/// Darkness of the level, in the level data
+pub struct LightingDefinition {
+    /// Light level without any source, 0.0 is black and 1.0 fully lit
+    pub ambient: f32,
+    /// Positions of the torches in the level map
+    pub torches: &'static [(usize, usize)],
+}
+
+/// Light of the current level, only present when the level is dark
+#[derive(Resource)]
+pub struct LightMap {
+    image: Handle<Image>,
+    width: usize,
+    height: usize,
+    solid: Vec<bool>,
+    ambient: f32,
+    torches: Vec<(usize, usize)>,
+    holds_visible: bool,
+}
+
+impl LightMap {
+    /// Index of a tile in the map, none outside of it
+    fn tile_index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.tile_index(x, y).is_some_and(|index| self.solid[index])
+    }
+
+    fn set_solid(&mut self, x: i32, y: i32, solid: bool) {
+        if let Some(index) = self.tile_index(x, y) {
+            self.solid[index] = solid;
+        }
+    }
+
+    /// No wall between the two tiles, the end tiles don't block the view so
+    /// the walls facing a light are lit
+    fn is_visible(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+        let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+        let (mut x, mut y) = from;
+        let mut error = dx + dy;
+
+        // Bresenham line between the tiles
+        loop {
+            if (x, y) == to {
+                return true;
+            }
+            if (x, y) != from && self.is_solid(x, y) {
+                return false;
+            }
+            let double = 2 * error;
+            if double >= dy {
+                error += dy;
+                x += sx;
+            }
+            if double <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Add a light around `center`, in tiles, keeping the brightest value
+    fn add_light(&self, light: &mut [f32], center: Vec2, radius: f32, intensity: f32) {
+        let origin = (center.x.round() as i32, center.y.round() as i32);
+        let reach = radius.ceil() as i32;
+
+        for y in origin.1 - reach..=origin.1 + reach {
+            for x in origin.0 - reach..=origin.0 + reach {
+                let Some(index) = self.tile_index(x, y) else {
+                    continue;
+                };
+                let distance = center.distance(Vec2::new(x as f32, y as f32));
+                if distance > radius || !self.is_visible(origin, (x, y)) {
+                    continue;
+                }
+
+                // Smooth fall off toward the edge of the light
+                let falloff = 1.0 - distance / radius;
+                light[index] = light[index].max(intensity * falloff * falloff);
+            }
+        }
+    }
+}
+
+/// Position in tiles of the level map, with y going down
+fn grid_position(translation: Vec3) -> Vec2 {
+    Vec2::new(
+        (translation.x - WORLD_LEFT) / TILE_SCALED,
+        (WORLD_TOP - translation.y) / TILE_SCALED,
+    )
+}
+
+/// Cover the level with darkness, or remove the light of the previous level
+pub fn spawn_lighting(commands: &mut Commands, images: &mut Assets<Image>, level: &Level, bounds: Rect) {
+    let Some(lighting) = &level.lighting else {
+        commands.remove_resource::<LightMap>();
+        return;
+    };
+
+    let height = level.map.lines().count();
+    let width = level.map.lines().map(|line| line.len()).max().unwrap_or(0);
+    let mut solid = vec![false; width * height];
+    for (y, line) in level.map.lines().enumerate() {
+        for (x, char) in line.chars().enumerate() {
+            solid[y * width + x] = SOLID_TILES.contains(char);
+        }
+    }
+
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Blend the light between the tiles
+    image.sampler = ImageSampler::linear();
+    let image = images.add(image);
+
+    commands.spawn((
+        SpriteBundle {
+            texture: image.clone(),
+            sprite: Sprite {
+                custom_size: Some(bounds.size()),
+                ..default()
+            },
+            transform: Transform::from_translation(bounds.center().extend(OVERLAY_DEPTH)),
+            ..default()
+        },
+        LevelEntity,
+    ));
+
+    for &(x, y) in lighting.torches {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: TORCH_COLOR,
+                    custom_size: Some(Vec2::splat(TORCH_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(grid_translation(x, y)),
+                ..default()
+            },
+            LevelEntity,
+        ));
+    }
+
+    commands.insert_resource(LightMap {
+        image,
+        width,
+        height,
+        solid,
+        ambient: lighting.ambient,
+        torches: lighting.torches.to_vec(),
+        holds_visible: false,
+    });
+}
+
+pub fn update_lighting(
+    time: Res<Time>,
+    light_map: Option<ResMut<LightMap>>,
+    mut images: ResMut<Assets<Image>>,
+    mut show_holds_event: EventReader<ShowHolds>,
+    player_query: Query<&Transform, With<Player>>,
+    hold_query: Query<&GlobalTransform, With<WallHold>>,
+    crumbling_query: Query<(&Crumbling, &Transform), Without<Player>>,
+) {
+    let Some(mut light_map) = light_map else {
+        show_holds_event.clear();
+        return;
+    };
+    for event in show_holds_event.read() {
+        light_map.holds_visible = event.0 == HoldsVisibility::Visible;
+    }
+
+    // The light goes through the broken blocks
+    for (crumbling, transform) in crumbling_query.iter() {
+        let tile = grid_position(transform.translation).round();
+        light_map.set_solid(tile.x as i32, tile.y as i32, crumbling.is_solid());
+    }
+
+    let mut light = vec![light_map.ambient; light_map.width * light_map.height];
+    let player = grid_position(player_query.single().translation);
+    light_map.add_light(&mut light, player, LANTERN_RADIUS, 1.0);
+
+    let elapsed = time.elapsed_seconds();
+    for (index, &(x, y)) in light_map.torches.iter().enumerate() {
+        let flicker = 1.0 - TORCH_FLICKER * (elapsed * 7.0 + index as f32 * 1.3).sin().abs();
+        light_map.add_light(&mut light, Vec2::new(x as f32, y as f32), TORCH_RADIUS, flicker);
+    }
+
+    if light_map.holds_visible {
+        for transform in hold_query.iter() {
+            let hold = grid_position(transform.translation());
+            if hold.distance(player) < HOLD_GLOW_RANGE {
+                light_map.add_light(&mut light, hold, HOLD_RADIUS, HOLD_INTENSITY);
+            }
+        }
+    }
+
+    let Some(image) = images.get_mut(&light_map.image) else {
+        return;
+    };
+    for (pixel, light) in image.data.chunks_exact_mut(4).zip(light) {
+        pixel[3] = ((1.0 - light.clamp(0.0, 1.0)) * 255.0) as u8;
+    }
+}
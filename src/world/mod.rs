@@ -11,9 +11,42 @@ use rand::{
 
 pub mod levels;
 pub mod holds;
+pub mod collectibles;
+pub mod crumbling;
+pub mod enemies;
+pub mod finish;
+pub mod hazards;
+pub mod lighting;
+pub mod one_way;
+pub mod parallax;
+pub mod platforms;
+pub mod springs;
+pub mod surfaces;
+pub mod themes;
+pub mod wind;
 
 use levels::*;
 use holds::*;
+use collectibles::*;
+use crumbling::*;
+use enemies::*;
+use finish::*;
+use hazards::*;
+use lighting::*;
+use one_way::*;
+use parallax::*;
+use platforms::*;
+use springs::*;
+use surfaces::*;
+use themes::*;
+use wind::*;
+
+use crate::player::camera::{CameraRig, follow_player};
+use crate::player::camera_effects::{CameraEffects, apply_camera_effects};
+use crate::player::Player;
+use crate::player::movement::player_movement;
+use crate::ApplicationState;
+use crate::save::SaveData;
 
 pub struct WorldPlugin;
 
@@ -21,25 +54,103 @@ const TILE_SIZE: f32 = 8.0;
 const TILE_SCALER: f32 = 4.0;
 pub const TILE_SCALED: f32 = TILE_SIZE * TILE_SCALER;
 
+const BACKGROUND_WIDTH: f32 = 416.0;
+
+/// Player start, for levels without a 'P' tile
+const PLAYER_START: Vec2 = Vec2::new(0.0, -20.0);
+
 /// World size definition
 const WORLD_HEIGHT: f32 = 600.0;
 const WORLD_TOP: f32 = WORLD_HEIGHT / 2.0;
+#[cfg(feature = "debug")]
 const WORLD_BOTTOM: f32 = -WORLD_TOP;
 // Use the golden ration here for the world size
 const WORLD_WIDTH: f32 = WORLD_HEIGHT * 1.618;
 const WORLD_RIGHT: f32 = WORLD_WIDTH / 2.0;
 const WORLD_LEFT: f32 = -WORLD_RIGHT;
 
+/// Index in `LEVELS` of the level being played
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub usize);
+
+/// Position where the player appears in the current level
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SpawnPoint(pub Vec2);
+
+/// Tag the entities to remove when changing level
+#[derive(Component)]
+pub struct LevelEntity;
+
+#[derive(Resource)]
+pub struct TileSet {
+    pub texture: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+}
+
+/// Area covered by the tiles of the current level, in world coordinates
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct LevelBounds(pub Rect);
+
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ShowHolds>();
-        app.add_systems(Startup, setup_world);
+        app.add_event::<LevelFinished>();
+        app.add_event::<PlayerDied>();
+        app.add_event::<Launched>();
+        app.init_resource::<CurrentLevel>();
+        app.insert_resource(SpawnPoint(PLAYER_START));
+        app.init_resource::<LevelCollectibles>();
+        app.init_resource::<LevelTheme>();
+        app.add_systems(Startup, (setup_world, setup_counter));
+        app.add_systems(Update, load_level.run_if(resource_changed::<CurrentLevel>));
         app.add_systems(Update, show_hold);
-        // app.add_systems(Update, debug_grid);
+        app.add_systems(Update, update_lighting.after(load_level));
+        app.add_systems(
+            Update,
+            (finish_detection, hazard_detection, checkpoint_detection, collect, launcher_detection)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(
+            Update,
+            (
+                save_collectibles.after(collect).after(checkpoint_detection).after(finish_detection),
+                restore_collectibles.after(hazard_detection),
+            )
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(Update, (animate_collectibles, update_counter));
+        app.add_systems(
+            Update,
+            (move_platforms, ride_platforms).run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(
+            Update,
+            ((chase_player, move_enemies, fly_enemies).chain(), enemy_contact)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(
+            Update,
+            (trigger_crumbling, update_crumbling)
+                .chain()
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            apply_wind
+                .after(player_movement)
+                .run_if(in_state(ApplicationState::InGame)),
+        );
+        app.add_systems(
+            Update,
+            parallax_scrolling
+                .after(follow_player)
+                .before(apply_camera_effects),
+        );
     }
 }
 
-fn debug_grid(mut gizmos: Gizmos) {
+#[cfg(feature = "debug")]
+pub fn debug_grid(mut gizmos: Gizmos) {
     for x in 0..512 {
         if x % TILE_SCALED as u32 == 0 {
             gizmos.line_2d(
@@ -79,67 +190,156 @@ fn setup_world(
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
     // 2d Camera
-    commands.spawn(Camera2dBundle::default());
-
-    // Background
-    let texture = asset_server.load("background_mountains.png");
-    commands.spawn(SpriteBundle{
-        texture: texture,
-        transform: Transform {
-            translation: Vec3::new(0.0, 200.0, -10.0),
-            scale: Vec3::new(TILE_SCALER, TILE_SCALER, 0.0),
-            ..default()
-        },
-        ..default()
-    });
+    commands.spawn((
+        Camera2dBundle::default(),
+        CameraRig::default(),
+        CameraEffects::default(),
+    ));
 
     // Tile-set
     let texture = asset_server.load("tiles.png");
     let layout =
         TextureAtlasLayout::from_grid(Vec2::new(TILE_SIZE, TILE_SIZE), 4, 7, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+    let layout = texture_atlas_layouts.add(layout);
+    commands.insert_resource(TileSet { texture, layout });
+}
+
+/// Replace the entities of the previous level by the ones of `CurrentLevel`
+#[allow(clippy::too_many_arguments)]
+fn load_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    tile_set: Res<TileSet>,
+    current_level: Res<CurrentLevel>,
+    save: Res<SaveData>,
+    level_entities: Query<Entity, With<LevelEntity>>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    mut camera_query: Query<&mut CameraRig>,
+) {
+    let level = &LEVELS[current_level.0];
+    let theme = theme(level.theme);
+    info!("Loading level {} with theme {}", level.id, theme.id);
+
+    for entity in level_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // Background
+    spawn_background_layers(&mut commands, &asset_server, theme);
+    commands.insert_resource(ClearColor(theme.clear_color));
+    commands.insert_resource(LevelTheme(theme));
+
+    let bounds = level_bounds(level.map);
+    commands.insert_resource(LevelBounds(bounds));
+    spawn_kill_plane(&mut commands, bounds);
+    spawn_lighting(&mut commands, &mut images, level, bounds);
+
+    let mut spawn_point = PLAYER_START;
+    let collected = save.collectibles.get(level.id);
+    let mut level_collectibles = LevelCollectibles::default();
 
-    for (y, line) in LEVEL_TRAINING.lines().enumerate() {
-        println!("line is {:?}", line);
+    for (y, line) in level.map.lines().enumerate() {
+        debug!("line is {:?}", line);
         for (x, char) in line.chars().enumerate() {
-            let translation = Vec3::new(
-                WORLD_LEFT + x as f32 * TILE_SCALED,
-                WORLD_TOP - y as f32 * TILE_SCALED,
-                0.0,
-            );
+            let translation = grid_translation(x, y);
             let scale = Vec3::new(TILE_SCALER, TILE_SCALER, 0.0);
-            let style = 3;
-            let idx = if char == 'B' {
-                Some(0 + style*4)
-            } else if char == 'b' {
-                Some(0 + (style+1)*4)
-            } else if char == 'R' {
-                Some(1 + style*4)
-            } else if char == 'r' {
-                Some(1 + (style+1)*4)
-            } else if char == 'G' {
-                Some(2 + style*4)
-            } else if char == 'g' {
-                Some(2 + (style+1)*4)
-            } else if char == 'D' {
-                Some(3 + style*4)
-            } else if char == 'd' {
-                Some(3 + (style+1)*4)
-            } else {
-                None
-            };
+            let idx = theme.wall_index(char);
             if let Some(idx) = idx {
                 let atlas = TextureAtlas {
-                    layout: texture_atlas_layout.clone(),
+                    layout: tile_set.layout.clone(),
                     index: idx,
                 };
-                spawn_wall(&mut commands, translation, scale, texture.clone(), atlas);
+                spawn_wall(&mut commands, translation, scale, tile_set.texture.clone(), atlas, Surface::from_tile(char));
+            } else if char == 'P' {
+                spawn_point = translation.truncate();
+            } else if char == 'F' {
+                spawn_finish(&mut commands, translation);
+            } else if char == '^' {
+                spawn_spikes(&mut commands, translation);
+            } else if char == '-' {
+                spawn_one_way_platform(&mut commands, translation);
+            } else if char == 'S' {
+                spawn_spring(&mut commands, translation);
+            } else if char == '<' {
+                spawn_bounce_pad(&mut commands, translation, -1.0);
+            } else if char == '>' {
+                spawn_bounce_pad(&mut commands, translation, 1.0);
+            } else if char == 'x' || char == 'X' {
+                spawn_crumbling(&mut commands, &tile_set, theme.crumbling_index(), translation, char == 'X', level.crumble_respawn);
+            } else if char == 'C' {
+                spawn_checkpoint(&mut commands, translation);
+            } else if char == '*' {
+                let is_collected = collected.is_some_and(|items| items.contains(&(x, y)));
+                spawn_collectible(&mut commands, translation, (x, y), is_collected);
+                level_collectibles.total += 1;
+                if is_collected {
+                    level_collectibles.collected += 1;
+                }
             }
         }
     }
+
+    for platform in level.platforms {
+        spawn_platform(&mut commands, &tile_set, theme.platform_index(), platform);
+    }
+    for wind_zone in level.wind_zones {
+        spawn_wind_zone(&mut commands, wind_zone);
+    }
+    for enemy in level.enemies {
+        spawn_enemy(&mut commands, enemy);
+    }
+
+    commands.insert_resource(SpawnPoint(spawn_point));
+    commands.insert_resource(level_collectibles);
+
+    // Move the player to the start of the level
+    for (mut transform, mut velocity) in player_query.iter_mut() {
+        transform.translation.x = spawn_point.x;
+        transform.translation.y = spawn_point.y;
+        *velocity = Velocity::zero();
+    }
+    for mut rig in camera_query.iter_mut() {
+        rig.snap();
+    }
+}
+
+/// Position in the world of a tile from the level map
+fn grid_translation(x: usize, y: usize) -> Vec3 {
+    Vec3::new(
+        WORLD_LEFT + x as f32 * TILE_SCALED,
+        WORLD_TOP - y as f32 * TILE_SCALED,
+        0.0,
+    )
+}
+
+/// Return the other entity of a collision starting with the player
+pub fn player_contact(event: &CollisionEvent, player: Entity) -> Option<Entity> {
+    match event {
+        CollisionEvent::Started(e1, e2, _) if *e1 == player => Some(*e2),
+        CollisionEvent::Started(e1, e2, _) if *e2 == player => Some(*e1),
+        _ => None,
+    }
+}
+
+/// Compute the area covered by a level from its grid dimensions
+fn level_bounds(level: &str) -> Rect {
+    let height = level.lines().count();
+    let width = level.lines().map(|line| line.len()).max().unwrap_or(0);
+
+    Rect::from_corners(
+        Vec2::new(
+            WORLD_LEFT - TILE_SCALED / 2.0,
+            WORLD_TOP + TILE_SCALED / 2.0,
+        ),
+        Vec2::new(
+            WORLD_LEFT + width as f32 * TILE_SCALED - TILE_SCALED / 2.0,
+            WORLD_TOP - height as f32 * TILE_SCALED + TILE_SCALED / 2.0,
+        ),
+    )
 }
 
-fn spawn_wall(commands: &mut Commands, translation: Vec3, scale: Vec3, texture: Handle<Image>, atlas: TextureAtlas) {
+fn spawn_wall(commands: &mut Commands, translation: Vec3, scale: Vec3, texture: Handle<Image>, atlas: TextureAtlas, surface: Surface) {
     let default_view_idx = atlas.index;
     commands
         .spawn((SpriteSheetBundle {
@@ -152,7 +352,9 @@ fn spawn_wall(commands: &mut Commands, translation: Vec3, scale: Vec3, texture:
             },
             ..default()
         },))
+        .insert(LevelEntity)
         .insert(RigidBody::Fixed)
         .insert(Collider::cuboid(TILE_SIZE/2.0, TILE_SIZE/2.0))
-        .insert(WallHold{key: rand::random(), wall_index: default_view_idx});
+        .insert(WallHold{key: rand::random(), wall_index: default_view_idx})
+        .insert(surface);
 }
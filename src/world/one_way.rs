@@ -0,0 +1,86 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Platforms the player can jump through from below and drop down from,
+/// placed with a '-' in the level map.
+/// The contacts are filtered with rapier physics hooks, only keeping the
+/// ones pushing the player up.
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::math::Vector;
+
+use crate::world::*;
+
+const ONE_WAY_COLOR: Color = Color::rgb(0.55, 0.4, 0.25);
+const ONE_WAY_HEIGHT: f32 = TILE_SCALED / 4.0;
+const DROP_THROUGH_DURATION: f32 = 0.3;
+// Tolerance on the contact normal to still be considered as from above
+const ALLOWED_ANGLE: f32 = 0.1;
+
+#[derive(Component)]
+pub struct OneWayPlatform;
+
+/// Let the player fall through the one-way platforms until the timer finishes
+#[derive(Component, Deref, DerefMut)]
+pub struct DropThrough(pub Timer);
+
+impl Default for DropThrough {
+    fn default() -> Self {
+        // Start finished, the player only drops when asked to
+        let mut timer = Timer::from_seconds(DROP_THROUGH_DURATION, TimerMode::Once);
+        let duration = timer.duration();
+        timer.tick(duration);
+        DropThrough(timer)
+    }
+}
+
+#[derive(SystemParam)]
+pub struct OneWayPlatformHooks<'w, 's> {
+    platforms: Query<'w, 's, (), With<OneWayPlatform>>,
+    drop_through: Query<'w, 's, &'static DropThrough>,
+}
+
+impl BevyPhysicsHooks for OneWayPlatformHooks<'_, '_> {
+    fn modify_solver_contacts(&self, context: ContactModificationContextView) {
+        let (platform, other, allowed_normal) = if self.platforms.contains(context.collider1()) {
+            (context.collider1(), context.collider2(), Vector::y())
+        } else if self.platforms.contains(context.collider2()) {
+            (context.collider2(), context.collider1(), -Vector::y())
+        } else {
+            return;
+        };
+
+        let dropping = self
+            .drop_through
+            .get(other)
+            .is_ok_and(|timer| !timer.finished());
+        if dropping {
+            debug!("Drop through {:?}", platform);
+            context.raw.solver_contacts.clear();
+        } else {
+            context.raw.update_as_oneway_platform(&allowed_normal, ALLOWED_ANGLE);
+        }
+    }
+}
+
+pub fn spawn_one_way_platform(commands: &mut Commands, translation: Vec3) {
+    // Only the top of the tile is solid
+    let translation = translation + Vec3::new(0.0, (TILE_SCALED - ONE_WAY_HEIGHT) / 2.0, 0.0);
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: ONE_WAY_COLOR,
+                custom_size: Some(Vec2::new(TILE_SCALED, ONE_WAY_HEIGHT)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(OneWayPlatform)
+        .insert(LevelEntity)
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(TILE_SCALED / 2.0, ONE_WAY_HEIGHT / 2.0))
+        .insert(ActiveHooks::MODIFY_SOLVER_CONTACTS);
+}
@@ -0,0 +1,75 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Parallax scrolling of the level background layers
+use bevy::prelude::*;
+
+use crate::world::*;
+
+#[derive(Component)]
+pub struct ParallaxLayer {
+    pub scroll_factor: Vec2,
+    pub offset: Vec2,
+    /// Width of one image copy when the layer is tiled
+    pub tile_width: Option<f32>,
+    /// Position of this copy relative to the one closest to the camera
+    pub tile_index: i32,
+}
+
+pub fn spawn_background_layers(commands: &mut Commands, asset_server: &AssetServer, theme: &Theme) {
+    for layer in theme.backgrounds {
+        let texture: Handle<Image> = asset_server.load(layer.image);
+        let (tile_width, tile_indices) = if layer.tiled {
+            (Some(BACKGROUND_WIDTH * TILE_SCALER), -1..=1)
+        } else {
+            (None, 0..=0)
+        };
+
+        for tile_index in tile_indices {
+            commands.spawn((
+                SpriteBundle {
+                    texture: texture.clone(),
+                    sprite: Sprite {
+                        color: layer.color,
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: layer.offset.extend(layer.depth),
+                        scale: Vec3::new(TILE_SCALER, TILE_SCALER, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ParallaxLayer {
+                    scroll_factor: layer.scroll_factor,
+                    offset: layer.offset,
+                    tile_width,
+                    tile_index,
+                },
+                LevelEntity,
+            ));
+        }
+    }
+}
+
+pub fn parallax_scrolling(
+    camera_query: Query<&Transform, (With<Camera>, Without<ParallaxLayer>)>,
+    mut layer_query: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let camera = camera_query.single().translation.truncate();
+
+    for (layer, mut transform) in layer_query.iter_mut() {
+        // The layer follows the camera, minus the fraction it should scroll
+        let mut position = layer.offset + camera * (Vec2::ONE - layer.scroll_factor);
+
+        if let Some(width) = layer.tile_width {
+            // Move the copies by whole image width to always cover the view
+            let shift = ((camera.x - position.x) / width).round() * width;
+            position.x += shift + layer.tile_index as f32 * width;
+        }
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
@@ -0,0 +1,155 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Kinematic platforms moving along the paths defined in the level data.
+/// The player standing on, or holding to, a platform is carried with it and
+/// keeps the platform velocity when leaving it.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::{Player, Support};
+use crate::world::*;
+
+#[derive(Component)]
+pub struct MovingPlatform {
+    waypoints: Vec<Vec2>,
+    target: usize,
+    forward: bool,
+    mode: PathMode,
+    speed: f32,
+    triggered: bool,
+}
+
+impl MovingPlatform {
+    /// Select the next waypoint to reach, return false when the path is over
+    fn next_target(&mut self) -> bool {
+        let last = self.waypoints.len() - 1;
+        match self.mode {
+            PathMode::PingPong => {
+                if self.forward && self.target == last {
+                    self.forward = false;
+                } else if !self.forward && self.target == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    self.target += 1;
+                } else {
+                    self.target -= 1;
+                }
+                true
+            }
+            PathMode::Loop => {
+                self.target = (self.target + 1) % self.waypoints.len();
+                true
+            }
+            PathMode::Triggered => {
+                if self.target < last {
+                    self.target += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+pub fn spawn_platform(commands: &mut Commands, tile_set: &TileSet, atlas_index: usize, definition: &PlatformDefinition) {
+    let (x, y) = definition.start;
+    let left = grid_translation(x, y);
+    let half_width = definition.width as f32 * TILE_SCALED / 2.0;
+    let center = left + Vec3::new(half_width - TILE_SCALED / 2.0, 0.0, 0.0);
+
+    let waypoints = definition
+        .waypoints
+        .iter()
+        .map(|(dx, dy)| center.truncate() + Vec2::new(*dx as f32, *dy as f32) * TILE_SCALED)
+        .collect::<Vec<_>>();
+
+    commands
+        .spawn(SpatialBundle::from_transform(Transform::from_translation(center)))
+        .insert(MovingPlatform {
+            waypoints,
+            target: 0,
+            forward: true,
+            mode: definition.mode,
+            speed: definition.speed,
+            triggered: definition.mode != PathMode::Triggered,
+        })
+        .insert(LevelEntity)
+        .insert(RigidBody::KinematicVelocityBased)
+        .insert(Velocity::zero())
+        .insert(Collider::cuboid(half_width, TILE_SCALED / 2.0))
+        // On the collider, where the player grabs the platform
+        .insert(WallHold { key: rand::random(), wall_index: atlas_index })
+        .with_children(|parent| {
+            for i in 0..definition.width {
+                parent.spawn(SpriteSheetBundle {
+                    texture: tile_set.texture.clone(),
+                    atlas: TextureAtlas {
+                        layout: tile_set.layout.clone(),
+                        index: atlas_index,
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(
+                            -half_width + TILE_SCALED / 2.0 + i as f32 * TILE_SCALED,
+                            0.0,
+                            0.0,
+                        ),
+                        scale: Vec3::new(TILE_SCALER, TILE_SCALER, 0.0),
+                        ..default()
+                    },
+                    ..default()
+                });
+            }
+        });
+}
+
+pub fn move_platforms(
+    mut query: Query<(Entity, &mut MovingPlatform, &Transform, &mut Velocity)>,
+    support_query: Query<&Support, With<Player>>,
+    time: Res<Time>,
+) {
+    let support = support_query.single();
+
+    for (entity, mut platform, transform, mut velocity) in query.iter_mut() {
+        if support.ground == Some(entity) {
+            platform.triggered = true;
+        }
+        if !platform.triggered || platform.waypoints.len() < 2 {
+            velocity.linvel = Vec2::ZERO;
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let step = platform.speed * time.delta_seconds();
+        if position.distance(platform.waypoints[platform.target]) <= step && !platform.next_target() {
+            velocity.linvel = Vec2::ZERO;
+            continue;
+        }
+
+        let direction = (platform.waypoints[platform.target] - position).normalize_or_zero();
+        velocity.linvel = direction * platform.speed;
+    }
+}
+
+pub fn ride_platforms(
+    mut player_query: Query<(&mut Transform, &mut Velocity, &mut Support), With<Player>>,
+    platform_query: Query<&Velocity, (With<MovingPlatform>, Without<Player>)>,
+    time: Res<Time>,
+) {
+    let (mut transform, mut velocity, mut support) = player_query.single_mut();
+
+    let carrier = support.ground.or(support.wall);
+    let carrier_velocity = carrier.and_then(|entity| platform_query.get(entity).ok());
+
+    if let Some(carrier_velocity) = carrier_velocity {
+        transform.translation += (carrier_velocity.linvel * time.delta_seconds()).extend(0.0);
+        support.carried = carrier_velocity.linvel;
+    } else if support.carried != Vec2::ZERO {
+        // Keep the momentum of the platform when leaving it
+        velocity.linvel += support.carried;
+        support.carried = Vec2::ZERO;
+    }
+}
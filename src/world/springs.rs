@@ -0,0 +1,99 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Elements launching the player when touched.
+/// Springs are placed with a 'S' in the level map and throw the player
+/// higher than a jump, bounce pads are placed with a '<' or '>' and throw the
+/// player diagonally in the given direction.
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::Player;
+use crate::player::movement::{JUMP_SPEED, MAX_RUNNING_SPEED};
+use crate::world::*;
+
+const SPRING_COLOR: Color = Color::rgb(0.9, 0.6, 0.1);
+const BOUNCE_PAD_COLOR: Color = Color::rgb(0.3, 0.8, 0.3);
+
+const SPRING_SPEED: f32 = 1.6 * JUMP_SPEED;
+const BOUNCE_PAD_SPEED: Vec2 = Vec2::new(1.5 * MAX_RUNNING_SPEED, 1.2 * JUMP_SPEED);
+
+// Time without player control after a launch, longer for the bounce pads to
+// not cancel the horizontal speed right away
+const SPRING_INHIBITION: Duration = Duration::from_millis(100);
+const BOUNCE_PAD_INHIBITION: Duration = Duration::from_millis(300);
+
+#[derive(Component)]
+pub struct Launcher {
+    velocity: Vec2,
+    inhibition: Duration,
+}
+
+/// Inform the player systems that the player must be thrown with the given
+/// velocity, without control for the inhibition duration
+#[derive(Event)]
+pub struct Launched {
+    pub velocity: Vec2,
+    pub inhibition: Duration,
+}
+
+pub fn spawn_spring(commands: &mut Commands, translation: Vec3) {
+    let launcher = Launcher {
+        velocity: Vec2::new(0.0, SPRING_SPEED),
+        inhibition: SPRING_INHIBITION,
+    };
+    spawn_launcher(commands, translation, launcher, SPRING_COLOR);
+}
+
+pub fn spawn_bounce_pad(commands: &mut Commands, translation: Vec3, direction: f32) {
+    let launcher = Launcher {
+        velocity: Vec2::new(direction * BOUNCE_PAD_SPEED.x, BOUNCE_PAD_SPEED.y),
+        inhibition: BOUNCE_PAD_INHIBITION,
+    };
+    spawn_launcher(commands, translation, launcher, BOUNCE_PAD_COLOR);
+}
+
+fn spawn_launcher(commands: &mut Commands, translation: Vec3, launcher: Launcher, color: Color) {
+    // Only the lower half of the tile, like a pad laying on the ground
+    let translation = translation - Vec3::new(0.0, TILE_SCALED / 4.0, 0.0);
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(TILE_SCALED, TILE_SCALED / 2.0)),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(launcher)
+        .insert(LevelEntity)
+        .insert(Collider::cuboid(TILE_SCALED / 2.0, TILE_SCALED / 4.0))
+        .insert(Sensor)
+        .insert(ActiveEvents::COLLISION_EVENTS);
+}
+
+pub fn launcher_detection(
+    mut collision_events: EventReader<CollisionEvent>,
+    player_query: Query<Entity, With<Player>>,
+    launcher_query: Query<&Launcher>,
+    mut launched_event: EventWriter<Launched>,
+) {
+    let player = player_query.single();
+
+    for event in collision_events.read() {
+        let Some(other) = player_contact(event, player) else {
+            continue;
+        };
+        if let Ok(launcher) = launcher_query.get(other) {
+            debug!("Player launched at {:?}", launcher.velocity);
+            launched_event.send(Launched {
+                velocity: launcher.velocity,
+                inhibition: launcher.inhibition,
+            });
+        }
+    }
+}
@@ -0,0 +1,57 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Materials of the level tiles, changing how the player moves on them.
+/// Each tile family of the level map has its own material:
+/// 'B' is normal, 'R' is a sticky wall, 'G' is mud and 'D' is ice.
+use bevy::prelude::*;
+
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Surface {
+    #[default]
+    Normal,
+    Ice,
+    Mud,
+    Sticky,
+}
+
+impl Surface {
+    /// Material of a tile from the level map
+    pub fn from_tile(tile: char) -> Surface {
+        match tile.to_ascii_uppercase() {
+            'R' => Surface::Sticky,
+            'G' => Surface::Mud,
+            'D' => Surface::Ice,
+            _ => Surface::Normal,
+        }
+    }
+
+    /// Factor applied to the running force on the ground
+    pub fn acceleration(&self) -> f32 {
+        match self {
+            Surface::Ice => 0.3,
+            Surface::Mud => 0.5,
+            Surface::Normal | Surface::Sticky => 1.0,
+        }
+    }
+
+    /// Factor applied to the force stopping the player on the ground
+    pub fn braking(&self) -> f32 {
+        match self {
+            Surface::Ice => 0.1,
+            Surface::Mud => 2.0,
+            Surface::Normal | Surface::Sticky => 1.0,
+        }
+    }
+
+    /// Factor applied to the maximal sliding speed on a wall
+    pub fn wall_sliding(&self) -> f32 {
+        match self {
+            Surface::Ice => 2.5,
+            Surface::Mud => 0.5,
+            Surface::Sticky => 0.2,
+            Surface::Normal => 1.0,
+        }
+    }
+}
@@ -0,0 +1,153 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Visual themes of the levels, picked by identifier in the level data.
+/// A theme selects the rows of `tiles.png` used by the walls, the background
+/// layers, the sky colour and the colour of the dust and debris particles.
+/// Unknown identifiers fall back to `DEFAULT_THEME`.
+use bevy::prelude::*;
+
+pub const DEFAULT_THEME: &str = "mountains";
+
+/// Background image scrolling behind the level
+pub struct BackgroundLayer {
+    pub image: &'static str,
+    /// Fraction of the camera movement applied to the layer on screen.
+    /// 0.0 stays in place with the camera, 1.0 moves like the tiles.
+    pub scroll_factor: Vec2,
+    /// Position of the layer when the camera is at the world origin
+    pub offset: Vec2,
+    pub depth: f32,
+    /// Repeat the image horizontally
+    pub tiled: bool,
+    pub color: Color,
+}
+
+pub struct Theme {
+    pub id: &'static str,
+    /// Row of `tiles.png` for the upper case walls, the lower case ones use
+    /// the next row
+    pub tile_style: usize,
+    pub backgrounds: &'static [BackgroundLayer],
+    pub clear_color: Color,
+    /// Colour of the dust raised by the player and of the crumbling debris
+    pub dust_color: Color,
+}
+
+impl Theme {
+    /// Index in `tiles.png` of a wall tile, none for the other characters
+    pub fn wall_index(&self, tile: char) -> Option<usize> {
+        let column = match tile.to_ascii_uppercase() {
+            'B' => 0,
+            'R' => 1,
+            'G' => 2,
+            'D' => 3,
+            _ => return None,
+        };
+        let row = if tile.is_ascii_lowercase() { self.tile_style + 1 } else { self.tile_style };
+        Some(column + row * 4)
+    }
+
+    /// Index in `tiles.png` of the moving platforms
+    pub fn platform_index(&self) -> usize {
+        2 + self.tile_style * 4
+    }
+
+    /// Index in `tiles.png` of the crumbling blocks, tinted when spawned
+    pub fn crumbling_index(&self) -> usize {
+        1 + (self.tile_style + 1) * 4
+    }
+}
+
+const MOUNTAINS_BACKGROUND: &[BackgroundLayer] = &[
+    BackgroundLayer {
+        image: "background_mountains.png",
+        scroll_factor: Vec2::new(0.1, 0.05),
+        offset: Vec2::new(400.0, 300.0),
+        depth: -20.0,
+        tiled: true,
+        color: Color::rgb(0.6, 0.6, 0.8),
+    },
+    BackgroundLayer {
+        image: "background_mountains.png",
+        scroll_factor: Vec2::new(0.3, 0.15),
+        offset: Vec2::new(0.0, 200.0),
+        depth: -10.0,
+        tiled: true,
+        color: Color::WHITE,
+    },
+];
+
+const CLIFF_BACKGROUND: &[BackgroundLayer] = &[
+    BackgroundLayer {
+        image: "background_mountains.png",
+        scroll_factor: Vec2::new(0.1, 0.05),
+        offset: Vec2::new(400.0, 250.0),
+        depth: -20.0,
+        tiled: true,
+        color: Color::rgb(0.7, 0.85, 0.9),
+    },
+    BackgroundLayer {
+        image: "background_mountains.png",
+        scroll_factor: Vec2::new(0.3, 0.15),
+        offset: Vec2::new(0.0, 150.0),
+        depth: -10.0,
+        tiled: true,
+        color: Color::rgb(0.7, 0.95, 0.7),
+    },
+];
+
+// Only a dark rock wall far behind the level
+const CAVE_BACKGROUND: &[BackgroundLayer] = &[
+    BackgroundLayer {
+        image: "background_mountains.png",
+        scroll_factor: Vec2::new(0.2, 0.2),
+        offset: Vec2::new(0.0, 100.0),
+        depth: -20.0,
+        tiled: true,
+        color: Color::rgb(0.15, 0.13, 0.17),
+    },
+];
+
+pub const THEMES: &[Theme] = &[
+    Theme {
+        id: "mountains",
+        tile_style: 3,
+        backgrounds: MOUNTAINS_BACKGROUND,
+        clear_color: Color::rgb(0.4, 0.4, 0.4),
+        dust_color: Color::rgb(0.8, 0.75, 0.65),
+    },
+    Theme {
+        id: "grassy_cliff",
+        tile_style: 1,
+        backgrounds: CLIFF_BACKGROUND,
+        clear_color: Color::rgb(0.55, 0.75, 0.9),
+        dust_color: Color::rgb(0.7, 0.8, 0.5),
+    },
+    Theme {
+        id: "cave",
+        tile_style: 4,
+        backgrounds: CAVE_BACKGROUND,
+        clear_color: Color::rgb(0.06, 0.05, 0.08),
+        dust_color: Color::rgb(0.55, 0.55, 0.6),
+    },
+];
+
+/// Theme with the given identifier, the default one when unknown
+pub fn theme(id: &str) -> &'static Theme {
+    THEMES.iter().find(|theme| theme.id == id).unwrap_or_else(|| {
+        warn!("Unknown theme {}, using {}", id, DEFAULT_THEME);
+        THEMES.iter().find(|theme| theme.id == DEFAULT_THEME).unwrap_or(&THEMES[0])
+    })
+}
+
+/// Theme of the current level
+#[derive(Resource)]
+pub struct LevelTheme(pub &'static Theme);
+
+impl Default for LevelTheme {
+    fn default() -> Self {
+        LevelTheme(theme(DEFAULT_THEME))
+    }
+}
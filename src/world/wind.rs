@@ -0,0 +1,56 @@
+/* SPDX-License-Identifier: MIT
+ * Copyright (c) 2024 Louis Mayencourt
+ */
+
+/// Areas pushing the player while inside them, defined in the level data.
+/// The wind is added to the `ExternalForce` once the player movement has
+/// computed its own force for the step.
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::{Player, PLAYER_MASS};
+use crate::world::*;
+
+const WIND_COLOR: Color = Color::rgba(0.8, 0.9, 1.0, 0.15);
+
+#[derive(Component)]
+pub struct WindZone {
+    acceleration: Vec2,
+}
+
+pub fn spawn_wind_zone(commands: &mut Commands, definition: &WindZoneDefinition) {
+    let (x, y) = definition.start;
+    let (width, height) = definition.size;
+    let size = Vec2::new(width as f32, height as f32) * TILE_SCALED;
+    // `start` is the top left tile of the zone
+    let center = grid_translation(x, y) + Vec3::new(size.x - TILE_SCALED, -(size.y - TILE_SCALED), 0.0) / 2.0;
+
+    commands
+        .spawn(SpriteBundle {
+            sprite: Sprite {
+                color: WIND_COLOR,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(center - Vec3::Z),
+            ..default()
+        })
+        .insert(WindZone { acceleration: definition.acceleration })
+        .insert(LevelEntity)
+        .insert(Collider::cuboid(size.x / 2.0, size.y / 2.0))
+        .insert(Sensor);
+}
+
+pub fn apply_wind(
+    mut player_query: Query<(Entity, &mut ExternalForce), With<Player>>,
+    zone_query: Query<(Entity, &WindZone)>,
+    rapier_ctx: Res<RapierContext>,
+) {
+    let (player, mut force) = player_query.single_mut();
+
+    for (entity, zone) in zone_query.iter() {
+        if rapier_ctx.intersection_pair(entity, player) == Some(true) {
+            force.force += zone.acceleration * PLAYER_MASS;
+        }
+    }
+}